@@ -64,12 +64,12 @@ fn choose_windowed(max: usize, window: usize, rng: &mut rand::rngs::StdRng) -> u
 }
 
 fn choose_unique_default(max: usize, duplicates: usize, rng: &mut rand::rngs::StdRng) -> usize {
-    let range = (0..max).flat_map(|x| std::iter::repeat(x).take(duplicates));
+    let range = (0..max).flat_map(|x| std::iter::repeat_n(x, duplicates));
     range.choose_unique(rng).len()
 }
 
 fn choose_unique_ahash(max: usize, duplicates: usize, rng: &mut rand::rngs::StdRng) -> usize {
-    let range = (0..max).flat_map(|x| std::iter::repeat(x).take(duplicates));
+    let range = (0..max).flat_map(|x| std::iter::repeat_n(x, duplicates));
 
     let hash_builder = core::hash::BuildHasherDefault::<ahash::AHasher>::default();
         let alloc = allocator_api2::alloc::Global;
@@ -79,12 +79,12 @@ fn choose_unique_ahash(max: usize, duplicates: usize, rng: &mut rand::rngs::StdR
 
 
 fn choose_unique_by_key_default(max: usize, duplicates: usize, rng: &mut rand::rngs::StdRng) -> usize {
-    let range = (0..max).flat_map(|x| std::iter::repeat(x).take(duplicates));
+    let range = (0..max).flat_map(|x| std::iter::repeat_n(x, duplicates));
     range.choose_unique_by_key(rng, |x|*x).len()
 }
 
 fn choose_unique_by_key_ahash(max: usize, duplicates: usize, rng: &mut rand::rngs::StdRng) -> usize {
-    let range = (0..max).flat_map(|x| std::iter::repeat(x).take(duplicates));
+    let range = (0..max).flat_map(|x| std::iter::repeat_n(x, duplicates));
 
     let hash_builder = core::hash::BuildHasherDefault::<ahash::AHasher>::default();
         let alloc = allocator_api2::alloc::Global;
@@ -1,6 +1,4 @@
-use iai_callgrind::{
-    library_benchmark, library_benchmark_group, main, FlamegraphConfig, LibraryBenchmarkConfig,
-};
+use iai_callgrind::{library_benchmark, library_benchmark_group, main};
 use kindness::Kindness;
 use rand::{seq::IteratorRandom, SeedableRng};
 
@@ -75,7 +73,7 @@ fn choose_windowed(max: usize, trials: usize) -> usize {
 #[bench::one_thousand(1000, 2)]
 fn choose_unique_default(max: usize, duplicates: usize) -> usize {
     let mut rng = get_rng(123);
-    let range = (0..max).flat_map(|x| std::iter::repeat(x).take(duplicates));
+    let range = (0..max).flat_map(|x| std::iter::repeat_n(x, duplicates));
     range.choose_unique(&mut rng).len()
 }
 
@@ -86,7 +84,7 @@ fn choose_unique_default(max: usize, duplicates: usize) -> usize {
 #[bench::one_thousand(1000, 2)]
 fn choose_unique_ahash(max: usize, duplicates: usize) -> usize {
     let mut rng = get_rng(123);
-    let range = (0..max).flat_map(|x| std::iter::repeat(x).take(duplicates));
+    let range = (0..max).flat_map(|x| std::iter::repeat_n(x, duplicates));
 
     let hash_builder = hashbrown::DefaultHashBuilder::default();
         let alloc = allocator_api2::alloc::Global;
@@ -12,6 +12,10 @@ pub fn criterion_benchmark(c: &mut Criterion) {
                 let mut rng = get_rng(123);
                 b.iter(|| sample_uniform(buckets, trials, &mut rng))
             });
+            c.bench_function(format!("sample_fast_dice_roller({buckets}, {trials})").as_str(), |b| {
+                let mut rng = get_rng(123);
+                b.iter(|| sample_fast_dice_roller(buckets, trials, &mut rng))
+            });
         }
     }
 }
@@ -35,12 +39,27 @@ fn sample_uniform(buckets: NonZero<u32>, trials: usize, rng: &mut rand::rngs::St
                 sum += next;
             }
         },
+        kindness::uniform::Uniform::FastDiceRoller(mut uniform) => {
+            for _ in 0..(black_box(trials)) {
+                let next = uniform.next(rng);
+                sum += next;
+            }
+        },
     }
 
 
     sum
 }
 
+fn sample_fast_dice_roller(buckets: NonZero<u32>, trials: usize, rng: &mut rand::rngs::StdRng) -> u32 {
+    let mut uniform = kindness::uniform::Uniform::new_fast_dice_roller(black_box(buckets));
+    let mut sum = 0;
+    for _ in 0..(black_box(trials)) {
+        sum += uniform.next(rng);
+    }
+    sum
+}
+
 // fn sample_uniform(buckets: NonZero<u32>, trials: usize, rng: &mut rand::rngs::StdRng) -> u32 {
 //     let mut uniform = rand::distributions::Uniform::new(0, black_box(buckets.get()) );
 
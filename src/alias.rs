@@ -0,0 +1,232 @@
+use alloc::vec::Vec;
+use core::num::NonZeroU32;
+
+use rand::{Rng, RngCore};
+
+use crate::coin_flipper::CoinFlipper;
+use crate::uniform::Uniform;
+
+/// A table for drawing weighted random elements in constant time.
+///
+/// Created by [`Kindness::collect_alias_table`](crate::Kindness::collect_alias_table).
+/// Uses Vose's alias method with exact integer arithmetic, so every element is chosen with exactly
+/// its weight divided by the total weight.
+#[derive(Debug, Clone)]
+pub struct AliasTable<T> {
+    items: Vec<T>,
+    columns: Vec<Column>,
+    /// The sum of the weights, which is the denominator of every column's threshold
+    total: u128,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Column {
+    /// The column's own item is chosen with probability `threshold / total`
+    threshold: u128,
+    /// The index of the item which is chosen otherwise
+    alias: u32,
+}
+
+impl<T> AliasTable<T> {
+    /// Builds a table from elements and their weights.
+    /// Elements with zero weight are dropped.
+    /// Returns [`None`] if no element has a positive weight.
+    /// Panics if more than `u32::MAX` elements have positive weights.
+    pub(crate) fn new<I: Iterator<Item = T>, F: FnMut(&T) -> u64>(
+        iterator: I,
+        mut weight: F,
+    ) -> Option<Self> {
+        let mut items = Vec::new();
+        let mut weights: Vec<u128> = Vec::new();
+        for item in iterator {
+            let w = weight(&item);
+            if w > 0 {
+                items.push(item);
+                weights.push(u128::from(w));
+            }
+        }
+
+        let n = u32::try_from(items.len()).expect("Too many elements for an alias table");
+        if n == 0 {
+            return None;
+        }
+        let total: u128 = weights.iter().sum();
+
+        // Scale each weight by `n` so that every column holds exactly `total`
+        let mut small: Vec<u32> = Vec::new();
+        let mut large: Vec<u32> = Vec::new();
+        for (index, w) in weights.iter_mut().enumerate() {
+            *w *= u128::from(n);
+            if *w < total {
+                small.push(index as u32);
+            } else {
+                large.push(index as u32);
+            }
+        }
+
+        let mut columns: Vec<Column> = (0..n)
+            .map(|alias| Column {
+                threshold: total,
+                alias,
+            })
+            .collect();
+
+        while let (Some(&s), Some(&l)) = (small.last(), large.last()) {
+            small.pop();
+            let scaled = weights[s as usize];
+            columns[s as usize] = Column {
+                threshold: scaled,
+                alias: l,
+            };
+            // The large item donates `total - scaled` to fill this column
+            weights[l as usize] -= total - scaled;
+            if weights[l as usize] < total {
+                large.pop();
+                small.push(l);
+            }
+        }
+        // The arithmetic is exact so any remaining items fill their own columns exactly
+
+        Some(Self {
+            items,
+            columns,
+            total,
+        })
+    }
+
+    /// The number of elements in the table.
+    /// This does not include elements which had zero weight.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    /// Always returns `false` because a table is only built if some element has a positive weight.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// The elements of the table.
+    #[must_use]
+    pub fn items(&self) -> &[T] {
+        &self.items
+    }
+
+    /// Returns a random element, chosen with probability proportional to its weight.
+    ///
+    /// To draw many elements, use [`sample_iter`](Self::sample_iter) which shares random bits between draws.
+    pub fn sample<R: Rng>(&self, rng: &mut R) -> &T {
+        self.sample_iter(rng).draw()
+    }
+
+    /// Returns an infinite iterator of random elements, each chosen with probability proportional to its weight.
+    ///
+    /// Unused random bits are kept between draws,
+    /// so each draw needs only a few more random bits than `log2(len)`.
+    pub fn sample_iter<R: RngCore>(&self, rng: R) -> AliasSampler<'_, T, R> {
+        let n = NonZeroU32::new(self.items.len() as u32).expect("Alias tables are never empty");
+        AliasSampler {
+            table: self,
+            uniform: Uniform::new(n),
+            coin_flipper: CoinFlipper::new(rng),
+        }
+    }
+}
+
+/// An infinite iterator of weighted random elements of an [`AliasTable`].
+///
+/// Created by [`AliasTable::sample_iter`].
+#[must_use]
+pub struct AliasSampler<'a, T, R: RngCore> {
+    table: &'a AliasTable<T>,
+    uniform: Uniform,
+    coin_flipper: CoinFlipper<R>,
+}
+
+impl<'a, T, R: RngCore> AliasSampler<'a, T, R> {
+    fn draw(&mut self) -> &'a T {
+        let index = self.uniform.next(&mut self.coin_flipper.rng) as usize;
+        let column = self.table.columns[index];
+        if column.threshold == self.table.total
+            || self.coin_flipper.gen_ratio(column.threshold, self.table.total)
+        {
+            &self.table.items[index]
+        } else {
+            &self.table.items[column.alias as usize]
+        }
+    }
+}
+
+impl<'a, T, R: RngCore> Iterator for AliasSampler<'a, T, R> {
+    type Item = &'a T;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        Some(self.draw())
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (usize::MAX, None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use crate::Kindness;
+
+    const RUNS: usize = 100000;
+
+    #[test]
+    fn test_alias_table_empty() {
+        assert!((0..0u64).collect_alias_table(|x| *x).is_none());
+        assert!((0..10u64).collect_alias_table(|_| 0).is_none());
+    }
+
+    #[test]
+    fn test_alias_table_drops_zero_weights() {
+        let table = (0..10u64).collect_alias_table(|x| x % 2).unwrap();
+        assert_eq!(table.items(), [1, 3, 5, 7, 9]);
+        let mut rng = StdRng::seed_from_u64(123);
+        assert!(table.sample_iter(&mut rng).take(1000).all(|x| x % 2 == 1));
+    }
+
+    #[test]
+    fn test_alias_table_distribution() {
+        let table = (0..10usize).collect_alias_table(|x| *x as u64 + 1).unwrap();
+        let mut counts = [0usize; 10];
+        let mut rng = StdRng::seed_from_u64(123);
+        for x in table.sample_iter(&mut rng).take(RUNS) {
+            counts[*x] += 1;
+        }
+
+        insta::assert_debug_snapshot!(counts);
+        for (x, count) in counts.into_iter().enumerate() {
+            let expected = RUNS * (x + 1) / 55;
+            assert!(count * 10 > expected * 9, "{x}: {count} vs {expected}");
+            assert!(count * 10 < expected * 11, "{x}: {count} vs {expected}");
+        }
+    }
+
+    #[test]
+    fn test_alias_table_extreme_weights() {
+        // The weights add up to more than u64::MAX, and the small weight should be drawn about 1 / 2001 of the time
+        let small = u64::MAX / 1000;
+        let table = [u64::MAX, small, u64::MAX].into_iter().collect_alias_table(|x| *x).unwrap();
+        let mut rng = StdRng::seed_from_u64(123);
+        let smalls = table.sample_iter(&mut rng).take(RUNS * 10).filter(|x| **x == small).count();
+        assert!(smalls > 400);
+        assert!(smalls < 600);
+    }
+
+    #[test]
+    fn test_alias_table_sample() {
+        let table = ["a", "b"].into_iter().collect_alias_table(|_| 1).unwrap();
+        let mut rng = StdRng::seed_from_u64(123);
+        let a = (0..RUNS).filter(|_| *table.sample(&mut rng) == "a").count();
+        assert!(a > RUNS * 45 / 100);
+        assert!(a < RUNS * 55 / 100);
+    }
+}
@@ -0,0 +1,185 @@
+//! A fixed-capacity vector stored inline, for use without an allocator.
+
+use core::fmt;
+use core::iter::FusedIterator;
+
+/// A vector with a fixed capacity of `N` elements which is stored inline and never allocates.
+///
+/// Returned by [`Kindness::choose_unique_array`](crate::Kindness::choose_unique_array)
+/// and [`Kindness::choose_multiple_array`](crate::Kindness::choose_multiple_array).
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ArrayVec<T, const N: usize> {
+    /// The first `len` elements are `Some` and the rest are `None`
+    items: [Option<T>; N],
+    len: usize,
+}
+
+/// The error returned when there are more elements than fit in an [`ArrayVec`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CapacityError;
+
+impl fmt::Display for CapacityError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("insufficient capacity")
+    }
+}
+
+#[cfg(any(test, feature = "std"))]
+impl std::error::Error for CapacityError {}
+
+impl<T, const N: usize> Default for ArrayVec<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, const N: usize> ArrayVec<T, N> {
+    /// Creates an empty vector.
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            items: core::array::from_fn(|_| None),
+            len: 0,
+        }
+    }
+
+    /// The number of elements.
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if there are no elements.
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// The maximum number of elements, `N`.
+    #[must_use]
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+
+    /// Returns `true` if there is no room for more elements.
+    #[must_use]
+    pub const fn is_full(&self) -> bool {
+        self.len == N
+    }
+
+    /// Adds an element to the end.
+    /// Returns the element back if the vector is full.
+    pub fn try_push(&mut self, item: T) -> Result<(), T> {
+        match self.items.get_mut(self.len) {
+            Some(slot) => {
+                *slot = Some(item);
+                self.len += 1;
+                Ok(())
+            }
+            None => Err(item),
+        }
+    }
+
+    /// Returns the element at `index`.
+    #[must_use]
+    pub fn get(&self, index: usize) -> Option<&T> {
+        self.items.get(index)?.as_ref()
+    }
+
+    /// Returns the element at `index`.
+    #[must_use]
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut T> {
+        self.items.get_mut(index)?.as_mut()
+    }
+
+    /// Returns an iterator over the elements.
+    pub fn iter(&self) -> impl ExactSizeIterator<Item = &T> + DoubleEndedIterator + '_ {
+        self.items[..self.len].iter().map(|item| {
+            item.as_ref()
+                .expect("Elements below len are always present")
+        })
+    }
+
+    /// Returns an iterator over the elements which allows modifying them.
+    pub fn iter_mut(&mut self) -> impl ExactSizeIterator<Item = &mut T> + DoubleEndedIterator + '_ {
+        self.items[..self.len].iter_mut().map(|item| {
+            item.as_mut()
+                .expect("Elements below len are always present")
+        })
+    }
+}
+
+impl<T, const N: usize> IntoIterator for ArrayVec<T, N> {
+    type Item = T;
+    type IntoIter = IntoIter<T, N>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter {
+            items: self.items.into_iter(),
+            remaining: self.len,
+        }
+    }
+}
+
+/// An iterator over the elements of an [`ArrayVec`].
+#[derive(Debug, Clone)]
+pub struct IntoIter<T, const N: usize> {
+    items: core::array::IntoIter<Option<T>, N>,
+    remaining: usize,
+}
+
+impl<T, const N: usize> Iterator for IntoIter<T, N> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.remaining = self.remaining.checked_sub(1)?;
+        self.items.next().flatten()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<T, const N: usize> ExactSizeIterator for IntoIter<T, N> {}
+
+impl<T, const N: usize> FusedIterator for IntoIter<T, N> {}
+
+#[cfg(test)]
+mod tests {
+    use super::ArrayVec;
+
+    #[test]
+    fn test_array_vec() {
+        let mut vec: ArrayVec<u32, 3> = ArrayVec::new();
+        assert!(vec.is_empty());
+        assert_eq!(vec.try_push(1), Ok(()));
+        assert_eq!(vec.try_push(2), Ok(()));
+        assert_eq!(vec.try_push(3), Ok(()));
+        assert!(vec.is_full());
+        assert_eq!(vec.try_push(4), Err(4));
+
+        *vec.get_mut(1).unwrap() = 20;
+        assert_eq!(vec.get(1), Some(&20));
+        assert_eq!(vec.get(3), None);
+        assert_eq!(vec.iter().len(), 3);
+        for x in vec.iter_mut() {
+            *x += 1;
+        }
+        let items: Vec<_> = vec.into_iter().collect();
+        assert_eq!(items, [2, 21, 4]);
+    }
+
+    #[test]
+    fn test_array_vec_into_iter_partial() {
+        let mut vec: ArrayVec<u32, 4> = ArrayVec::new();
+        vec.try_push(1).unwrap();
+        vec.try_push(2).unwrap();
+        let mut iter = vec.into_iter();
+        assert_eq!(iter.len(), 2);
+        assert_eq!(iter.next(), Some(1));
+        assert_eq!(iter.next(), Some(2));
+        assert_eq!(iter.next(), None);
+        assert_eq!(iter.len(), 0);
+    }
+}
@@ -0,0 +1,322 @@
+//! Bootstrap resampling: drawing replicates of a collection with replacement.
+
+use alloc::vec::Vec;
+use core::fmt;
+use core::iter::FusedIterator;
+use core::num::NonZeroU32;
+
+use rand::RngCore;
+
+use crate::uniform::Uniform;
+
+/// The number of indices drawn at once with [`Uniform::fill`]
+const BUFFER_LENGTH: usize = 64;
+
+/// Draws bootstrap replicates of a collection one at a time.
+///
+/// Each replicate is `n` elements drawn uniformly with replacement from the `n` elements of the collection,
+/// and can be read either as the multiplicity of each element or as the indices of the draws.
+/// Nothing is drawn until a replicate is read, and only one replicate is held at a time,
+/// so `n × replicates` indices are never stored.
+///
+/// Created by [`Kindness::bootstrap`](crate::Kindness::bootstrap).
+pub struct Bootstrap<T, R> {
+    items: Vec<T>,
+    rng: R,
+    /// `None` if there are no items
+    uniform: Option<Uniform>,
+    remaining: usize,
+    /// Reused by each replicate for its multiplicities
+    counts: Vec<usize>,
+}
+
+impl<T, R> Bootstrap<T, R> {
+    pub(crate) fn new(items: Vec<T>, rng: R, replicates: usize) -> Self {
+        let n = u32::try_from(items.len()).expect("Cannot bootstrap more than u32::MAX elements");
+        Self {
+            items,
+            rng,
+            uniform: NonZeroU32::new(n).map(Uniform::new),
+            remaining: replicates,
+            counts: Vec::new(),
+        }
+    }
+
+    /// The elements which are resampled.
+    #[must_use]
+    pub fn items(&self) -> &[T] {
+        &self.items
+    }
+
+    /// The number of replicates which have not been drawn yet.
+    #[must_use]
+    pub const fn remaining(&self) -> usize {
+        self.remaining
+    }
+
+    /// Returns the next replicate, or [`None`] if every replicate has been drawn.
+    ///
+    /// The replicate borrows the bootstrap, so it must be read before the next one is drawn.
+    pub fn next_replicate(&mut self) -> Option<Replicate<'_, T, R>> {
+        self.remaining = self.remaining.checked_sub(1)?;
+        Some(Replicate { bootstrap: self })
+    }
+}
+
+impl<T: fmt::Debug, R> fmt::Debug for Bootstrap<T, R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Bootstrap")
+            .field("items", &self.items)
+            .field("remaining", &self.remaining)
+            .finish_non_exhaustive()
+    }
+}
+
+/// One bootstrap replicate, drawn when it is read.
+///
+/// Created by [`Bootstrap::next_replicate`].
+#[must_use = "a replicate is only drawn when it is read"]
+pub struct Replicate<'a, T, R> {
+    bootstrap: &'a mut Bootstrap<T, R>,
+}
+
+impl<'a, T, R: RngCore> Replicate<'a, T, R> {
+    /// The number of times each element was drawn, in the order of [`Bootstrap::items`].
+    /// The counts follow a multinomial distribution and add up to the number of elements.
+    ///
+    /// Uses the same random numbers as [`Replicate::indices`], so the counts are the histogram of those indices.
+    #[must_use]
+    pub fn counts(self) -> &'a [usize] {
+        let Bootstrap {
+            items,
+            rng,
+            uniform,
+            counts,
+            ..
+        } = self.bootstrap;
+        counts.clear();
+        counts.resize(items.len(), 0);
+        for index in Indices::new(rng, uniform.as_mut(), items.len()) {
+            counts[index] += 1;
+        }
+        counts
+    }
+
+    /// The indices of the drawn elements, drawn lazily in batches.
+    pub fn indices(self) -> Indices<'a, R> {
+        let Bootstrap {
+            items,
+            rng,
+            uniform,
+            ..
+        } = self.bootstrap;
+        Indices::new(rng, uniform.as_mut(), items.len())
+    }
+
+    /// The drawn elements.
+    pub fn iter(self) -> impl ExactSizeIterator<Item = &'a T> + FusedIterator {
+        let Bootstrap {
+            items,
+            rng,
+            uniform,
+            ..
+        } = self.bootstrap;
+        let items: &'a [T] = items;
+        Indices::new(rng, uniform.as_mut(), items.len()).map(move |index| &items[index])
+    }
+}
+
+impl<T, R> fmt::Debug for Replicate<'_, T, R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Replicate").finish_non_exhaustive()
+    }
+}
+
+/// The indices of the elements drawn for one bootstrap replicate.
+///
+/// Created by [`Replicate::indices`].
+pub struct Indices<'a, R> {
+    rng: &'a mut R,
+    uniform: Option<&'a mut Uniform>,
+    buffer: [u32; BUFFER_LENGTH],
+    /// The position of the next index in the buffer
+    position: usize,
+    /// The number of indices in the buffer
+    filled: usize,
+    /// The number of indices which have not been put in the buffer yet
+    undrawn: usize,
+}
+
+impl<'a, R> Indices<'a, R> {
+    fn new(rng: &'a mut R, uniform: Option<&'a mut Uniform>, len: usize) -> Self {
+        Self {
+            rng,
+            uniform,
+            buffer: [0; BUFFER_LENGTH],
+            position: 0,
+            filled: 0,
+            undrawn: len,
+        }
+    }
+}
+
+impl<R: RngCore> Iterator for Indices<'_, R> {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.position == self.filled {
+            // There is always a distribution if there are indices to draw
+            let uniform = self.uniform.as_deref_mut().filter(|_| self.undrawn > 0)?;
+            self.filled = self.undrawn.min(BUFFER_LENGTH);
+            self.undrawn -= self.filled;
+            self.position = 0;
+            uniform.fill(self.rng, &mut self.buffer[..self.filled]);
+        }
+        let index = self.buffer[self.position] as usize;
+        self.position += 1;
+        Some(index)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.filled - self.position + self.undrawn;
+        (remaining, Some(remaining))
+    }
+}
+
+impl<R: RngCore> ExactSizeIterator for Indices<'_, R> {}
+
+impl<R: RngCore> FusedIterator for Indices<'_, R> {}
+
+impl<R> fmt::Debug for Indices<'_, R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Indices")
+            .field("remaining", &(self.filled - self.position + self.undrawn))
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::num::NonZeroU32;
+
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use crate::uniform::Uniform;
+    use crate::Kindness;
+
+    const RUNS: usize = 10000;
+    const LENGTH: usize = 100;
+    const LOWER_TOLERANCE: usize = 60;
+    const UPPER_TOLERANCE: usize = 140;
+
+    #[test]
+    fn test_bootstrap_counts() {
+        let mut totals: [usize; LENGTH] = [0; LENGTH];
+        let mut rng = StdRng::seed_from_u64(123);
+        let mut bootstrap = (0..LENGTH).bootstrap(&mut rng, RUNS);
+        assert_eq!(bootstrap.remaining(), RUNS);
+
+        while let Some(replicate) = bootstrap.next_replicate() {
+            let counts = replicate.counts();
+            assert_eq!(counts.len(), LENGTH);
+            assert_eq!(counts.iter().sum::<usize>(), LENGTH);
+            for (total, count) in totals.iter_mut().zip(counts) {
+                *total += count;
+            }
+        }
+        assert_eq!(bootstrap.remaining(), 0);
+
+        insta::assert_debug_snapshot!(totals);
+        for x in totals {
+            assert!(x > LOWER_TOLERANCE * LENGTH);
+            assert!(x < UPPER_TOLERANCE * LENGTH);
+        }
+    }
+
+    #[test]
+    fn test_bootstrap_counts_match_indices() {
+        let mut counts_rng = StdRng::seed_from_u64(123);
+        let mut indices_rng = StdRng::seed_from_u64(123);
+        let mut by_counts = (0..LENGTH).bootstrap(&mut counts_rng, 10);
+        let mut by_indices = (0..LENGTH).bootstrap(&mut indices_rng, 10);
+
+        while let Some(replicate) = by_counts.next_replicate() {
+            let mut expected = [0usize; LENGTH];
+            for index in by_indices.next_replicate().unwrap().indices() {
+                expected[index] += 1;
+            }
+            assert_eq!(replicate.counts(), expected.as_slice());
+        }
+    }
+
+    #[test]
+    fn test_bootstrap_indices_use_uniform() {
+        let mut rng = StdRng::seed_from_u64(123);
+        let mut bootstrap = (0..LENGTH).bootstrap(&mut rng, 2);
+        let mut drawn = Vec::new();
+        while let Some(replicate) = bootstrap.next_replicate() {
+            let indices = replicate.indices();
+            assert_eq!(indices.len(), LENGTH);
+            drawn.extend(indices);
+        }
+
+        let mut expected_rng = StdRng::seed_from_u64(123);
+        let mut uniform = Uniform::new(NonZeroU32::new(LENGTH as u32).unwrap());
+        let expected: Vec<_> = (0..2 * LENGTH)
+            .map(|_| uniform.next(&mut expected_rng) as usize)
+            .collect();
+        assert_eq!(drawn, expected);
+    }
+
+    #[test]
+    fn test_bootstrap_iter() {
+        let mut rng = StdRng::seed_from_u64(123);
+        let words = ["a", "b", "c"];
+        let mut bootstrap = words.iter().bootstrap(&mut rng, 5);
+        while let Some(replicate) = bootstrap.next_replicate() {
+            let resampled: Vec<_> = replicate.iter().collect();
+            assert_eq!(resampled.len(), 3);
+            assert!(resampled.iter().all(|x| words.contains(x)));
+        }
+    }
+
+    #[test]
+    fn test_bootstrap_unhinted() {
+        let mut rng = StdRng::seed_from_u64(123);
+        let mut bootstrap = (0..LENGTH).filter(|x| x % 2 == 0).bootstrap(&mut rng, 5);
+        while let Some(replicate) = bootstrap.next_replicate() {
+            let counts = replicate.counts();
+            assert_eq!(counts.len(), LENGTH / 2);
+            assert_eq!(counts.iter().sum::<usize>(), LENGTH / 2);
+        }
+    }
+
+    #[test]
+    fn test_bootstrap_empty() {
+        let mut rng = StdRng::seed_from_u64(123);
+        let mut bootstrap = (0..0).bootstrap(&mut rng, 3);
+        let mut replicates = 0;
+        while let Some(replicate) = bootstrap.next_replicate() {
+            assert!(replicate.counts().is_empty());
+            replicates += 1;
+        }
+        assert_eq!(replicates, 3);
+
+        let mut bootstrap = (0..0).bootstrap(&mut rng, 1);
+        assert_eq!(bootstrap.next_replicate().unwrap().indices().next(), None);
+    }
+
+    #[test]
+    fn test_bootstrap_partial_replicate() {
+        let mut rng = StdRng::seed_from_u64(123);
+        let mut bootstrap = (0..LENGTH).bootstrap(&mut rng, 2);
+        let mut indices = bootstrap.next_replicate().unwrap().indices();
+        assert!(indices.next().unwrap() < LENGTH);
+        assert_eq!(indices.len(), LENGTH - 1);
+        drop(indices);
+        // Dropping a replicate early does not affect the next one
+        let counts = bootstrap.next_replicate().unwrap().counts();
+        assert_eq!(counts.iter().sum::<usize>(), LENGTH);
+        assert!(bootstrap.next_replicate().is_none());
+    }
+}
@@ -0,0 +1,16 @@
+/// A randomly chosen best element, along with statistics about how it was chosen.
+///
+/// Returned by the `_with_stats` methods of [`Kindness`](crate::Kindness), such as
+/// [`choose_max_by_key_with_stats`](crate::Kindness::choose_max_by_key_with_stats).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Chosen<T, K = (), C = usize> {
+    /// The chosen element
+    pub item: T,
+    /// The key of the chosen element, or `()` if elements were compared directly
+    pub key: K,
+    /// The number of elements which were tied for best, including the chosen element.
+    /// The chosen element was picked uniformly at random from these.
+    pub ties: C,
+    /// The total number of elements in the iterator
+    pub total_seen: C,
+}
@@ -1,6 +1,8 @@
 use core::ops::Not;
 
-use rand::RngCore;
+use rand::{Rng, RngCore};
+
+use crate::counter::Counter;
 
 pub(crate) struct CoinFlipper<R: RngCore> {
     pub rng: R,
@@ -32,7 +34,8 @@ impl<R: RngCore> CoinFlipper<R> {
             }
 
             let r = self.chunk >> lz;
-            self.chunk <<= digits;
+            // `digits` may be 32, which would overflow a normal shift
+            self.chunk = self.chunk.checked_shl(digits).unwrap_or_default();
             self.chunk_remaining -= digits;
 
             if r <= inclusive_upper_bound {
@@ -41,20 +44,34 @@ impl<R: RngCore> CoinFlipper<R> {
         }
     }
 
+    /// Returns a random index in `0..ubound`
+    /// Uses 32-bit sampling where possible, primarily in order to produce the same output
+    /// on 32-bit and 64-bit platforms.
+    /// Panics if `ubound == 0`
+    #[inline]
+    pub fn gen_index_below<C: Counter>(&mut self, ubound: C) -> C {
+        debug_assert_ne!(ubound, C::ZERO);
+        if let Some(inclusive_upper_bound) = ubound.wrapping_sub(C::ONE).try_into_u32() {
+            C::from_usize(self.gen_index(inclusive_upper_bound) as usize)
+        } else {
+            C::random_below(&mut self.rng, ubound)
+        }
+    }
+
     #[inline]
     /// Returns true with a probability of 1 / d
     /// Uses an expected two bits of randomness
     /// Panics if d == 0
-    pub fn gen_ratio_one_over(&mut self, d: usize) -> bool {
-        debug_assert_ne!(d, 0);
+    pub fn gen_ratio_one_over<C: Counter>(&mut self, d: C) -> bool {
+        debug_assert_ne!(d, C::ZERO);
         // This uses the same logic as `gen_ratio` but is optimized for the case that
         // the starting numerator is one (which it always is for `Sequence::Choose()`)
 
         // In this case (but not `gen_ratio`), this way of calculating c is always accurate
-        let c = (usize::BITS - 1 - d.leading_zeros()).min(32);
+        let c = (C::BITS - 1 - d.leading_zeros()).min(32);
 
         if self.flip_c_heads(c) {
-            let numerator = 1 << c;
+            let numerator = C::ONE.wrapping_shl(c);
             return self.gen_ratio(numerator, d);
         } else {
             return false;
@@ -64,7 +81,7 @@ impl<R: RngCore> CoinFlipper<R> {
     #[inline]
     /// Returns true with a probability of n / d
     /// Uses an expected two bits of randomness
-    fn gen_ratio(&mut self, mut n: usize, d: usize) -> bool {
+    pub fn gen_ratio<C: Counter>(&mut self, mut n: C, d: C) -> bool {
         // Explanation:
         // We are trying to return true with a probability of n / d
         // If n >= d, we can just return true
@@ -107,15 +124,15 @@ impl<R: RngCore> CoinFlipper<R> {
                 // All heads
                 // Set n to n * 2^c
                 // If 2n >= d, the while loop will exit and we will return `true`
-                // If n * 2^c > `usize::MAX` we always return `true` anyway
-                n = n.saturating_mul(2_usize.pow(c));
+                // If n * 2^c > `C::MAX` we always return `true` anyway
+                n = n.saturating_shl(c);
             } else {
                 //At least one tail
                 if c == 1 {
                     // Calculate 2n - d.
-                    // We need to use wrapping as 2n might be greater than `usize::MAX`
+                    // We need to use wrapping as 2n might be greater than `C::MAX`
                     let next_n = n.wrapping_add(n).wrapping_sub(d);
-                    if next_n == 0 || next_n > n {
+                    if next_n == C::ZERO || next_n > n {
                         // This will happen if 2n < d
                         return false;
                     }
@@ -220,7 +237,7 @@ pub mod tests {
         let min  = *results.iter().min().unwrap();
 
         assert!(max <= 9);
-        assert!(min >= 0);
+        assert_eq!(min, 0);
 
         let mut distribution = [0usize;10];
         let mut differences: [usize; 19] = [0usize;19];
@@ -237,4 +254,15 @@ pub mod tests {
         insta::assert_debug_snapshot!("distribution", distribution);
         insta::assert_debug_snapshot!("differences", differences );
     }
+
+    #[test]
+    pub fn test_coin_flipper_gen_index_large_bounds() {
+        let rng = StdRng::seed_from_u64(123);
+        let mut flipper = CoinFlipper::new(rng);
+        assert!((0..1000).any(|_| flipper.gen_index(u32::MAX) >= 1 << 31));
+        for _ in 0..1000 {
+            assert!(flipper.gen_index_below((1usize << 31) + 1) <= 1 << 31);
+            assert!(flipper.gen_index_below(u128::MAX) < u128::MAX);
+        }
+    }
 }
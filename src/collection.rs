@@ -0,0 +1,120 @@
+use allocator_api2::alloc::Allocator;
+use hashbrown::HashTable;
+use rand::Rng;
+
+use crate::coin_flipper::CoinFlipper;
+
+/// Tables where fewer than one in this many buckets are full are sampled with a linear scan
+/// because probing would need too many attempts to find a full bucket.
+pub(crate) const SPARSE_RATIO: usize = 8;
+
+/// Methods for choosing random elements from hashbrown collections.
+///
+/// This is only implemented for [`HashTable`], which exposes its buckets.
+/// [`hashbrown::HashMap`] and [`hashbrown::HashSet`] do not, so choosing from them would need a linear scan,
+/// which [`Kindness::choose_item`](crate::Kindness::choose_item) on their iterators already does.
+/// For constant expected time, store the entries in a `HashTable<(K, V)>` or `HashTable<T>` instead.
+pub trait CollectionKindness {
+    /// The type of a reference to an element of the collection.
+    type Item<'a>
+    where
+        Self: 'a;
+
+    /// Return a random element of the collection.
+    /// Returns none if the collection is empty.
+    ///
+    /// This probes random buckets until it finds a full one,
+    /// which takes expected constant time unless the table is very sparse (e.g. after many removals),
+    /// in which case it falls back to a linear scan.
+    ///
+    /// Which element is returned for a given seed depends on the hasher,
+    /// so it is not covered by the value stability guarantee.
+    fn choose_item<R: Rng>(&self, rng: &mut R) -> Option<Self::Item<'_>>;
+}
+
+impl<T, A: Allocator> CollectionKindness for HashTable<T, A> {
+    type Item<'a>
+        = &'a T
+    where
+        Self: 'a;
+
+    fn choose_item<R: Rng>(&self, rng: &mut R) -> Option<Self::Item<'_>> {
+        let len = self.len();
+        if len == 0 {
+            return None;
+        }
+        let mut coin_flipper = CoinFlipper::new(rng);
+        let buckets = self.num_buckets();
+
+        if len.saturating_mul(SPARSE_RATIO) < buckets {
+            return self.iter().nth(coin_flipper.gen_index_below(len));
+        }
+
+        // The number of buckets is a power of two, so each probe uses exactly log2(buckets) random bits
+        loop {
+            if let Some(item) = self.get_bucket(coin_flipper.gen_index_below(buckets)) {
+                return Some(item);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::hash::{BuildHasher, BuildHasherDefault};
+    use std::collections::hash_map::DefaultHasher;
+
+    use hashbrown::HashTable;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use super::CollectionKindness;
+
+    const RUNS: usize = 10000;
+    const LENGTH: usize = 100;
+    const LOWER_TOLERANCE: usize = 60;
+    const UPPER_TOLERANCE: usize = 140;
+
+    type Hasher = BuildHasherDefault<DefaultHasher>;
+
+    fn table_of(range: core::ops::Range<usize>) -> HashTable<usize> {
+        let hasher = Hasher::default();
+        let mut table = HashTable::new();
+        for x in range {
+            table.insert_unique(hasher.hash_one(x), x, |y| hasher.hash_one(y));
+        }
+        table
+    }
+
+    fn assert_uniform(mut choose: impl FnMut(&mut StdRng) -> usize) {
+        let mut counts: [usize; LENGTH] = [0; LENGTH];
+        let mut rng = StdRng::seed_from_u64(123);
+        for _ in 0..RUNS {
+            counts[choose(&mut rng)] += 1;
+        }
+        for x in counts {
+            assert!(x > LOWER_TOLERANCE);
+            assert!(x < UPPER_TOLERANCE);
+        }
+    }
+
+    #[test]
+    fn test_choose_item_empty() {
+        let mut rng = StdRng::seed_from_u64(123);
+        assert_eq!(table_of(0..0).choose_item(&mut rng), None);
+    }
+
+    #[test]
+    fn test_hash_table_choose_item() {
+        let table = table_of(0..LENGTH);
+        assert!(table.len() * super::SPARSE_RATIO >= table.num_buckets());
+        assert_uniform(|rng| *table.choose_item(rng).unwrap());
+    }
+
+    #[test]
+    fn test_sparse_hash_table_choose_item() {
+        let mut table = table_of(0..(LENGTH * 20));
+        table.retain(|x| *x < LENGTH);
+        assert!(table.len() * super::SPARSE_RATIO < table.num_buckets());
+        assert_uniform(|rng| *table.choose_item(rng).unwrap());
+    }
+}
@@ -0,0 +1,119 @@
+use rand::{Rng, RngCore};
+
+mod private {
+    pub trait Sealed {}
+}
+
+/// An unsigned integer type which can be used to count elements.
+///
+/// The [`Kindness`](crate::Kindness) methods count with `usize`, which means that
+/// later elements become slightly more likely once more than `usize::MAX` elements have been seen.
+/// Counting with `u64` or `u128` (see [`Kindness::wide_counter`](crate::Kindness::wide_counter)) avoids this.
+///
+/// This trait is sealed and cannot be implemented outside this crate.
+pub trait Counter: private::Sealed + Copy + Ord + core::fmt::Debug {
+    /// Zero
+    const ZERO: Self;
+    /// One
+    const ONE: Self;
+    /// The largest value of this type
+    const MAX: Self;
+    /// The size of this type in bits
+    const BITS: u32;
+
+    #[doc(hidden)]
+    fn leading_zeros(self) -> u32;
+    #[doc(hidden)]
+    fn saturating_add(self, rhs: Self) -> Self;
+    #[doc(hidden)]
+    fn wrapping_add(self, rhs: Self) -> Self;
+    #[doc(hidden)]
+    fn wrapping_sub(self, rhs: Self) -> Self;
+    #[doc(hidden)]
+    fn wrapping_shl(self, rhs: u32) -> Self;
+    #[doc(hidden)]
+    fn from_usize(n: usize) -> Self;
+    #[doc(hidden)]
+    fn try_into_usize(self) -> Option<usize>;
+    #[doc(hidden)]
+    fn try_into_u32(self) -> Option<u32>;
+    #[doc(hidden)]
+    fn random_below<R: RngCore>(rng: &mut R, ubound: Self) -> Self;
+
+    /// Multiplies by `2^c`, saturating at the numeric bounds instead of overflowing.
+    #[doc(hidden)]
+    #[inline]
+    fn saturating_shl(self, c: u32) -> Self {
+        if self.leading_zeros() >= c {
+            self.wrapping_shl(c)
+        } else {
+            Self::MAX
+        }
+    }
+}
+
+macro_rules! impl_counter {
+    ($ty:ty) => {
+        impl private::Sealed for $ty {}
+
+        impl Counter for $ty {
+            const ZERO: Self = 0;
+            const ONE: Self = 1;
+            const MAX: Self = <$ty>::MAX;
+            const BITS: u32 = <$ty>::BITS;
+
+            #[inline]
+            fn leading_zeros(self) -> u32 {
+                self.leading_zeros()
+            }
+
+            #[inline]
+            fn saturating_add(self, rhs: Self) -> Self {
+                self.saturating_add(rhs)
+            }
+
+            #[inline]
+            fn wrapping_add(self, rhs: Self) -> Self {
+                self.wrapping_add(rhs)
+            }
+
+            #[inline]
+            fn wrapping_sub(self, rhs: Self) -> Self {
+                self.wrapping_sub(rhs)
+            }
+
+            #[inline]
+            fn wrapping_shl(self, rhs: u32) -> Self {
+                self.wrapping_shl(rhs)
+            }
+
+            #[inline]
+            fn from_usize(n: usize) -> Self {
+                Self::try_from(n).unwrap_or(Self::MAX)
+            }
+
+            #[inline]
+            fn try_into_usize(self) -> Option<usize> {
+                usize::try_from(self).ok()
+            }
+
+            #[inline]
+            fn try_into_u32(self) -> Option<u32> {
+                u32::try_from(self).ok()
+            }
+
+            #[inline]
+            fn random_below<R: RngCore>(rng: &mut R, ubound: Self) -> Self {
+                rng.random_range(0..ubound)
+            }
+        }
+    };
+}
+
+impl_counter!(usize);
+impl_counter!(u64);
+impl_counter!(u128);
+// Lets the tests count exactly as `usize` does on 32-bit targets.
+// It is not public, as `u32` would be narrower than `usize` for `wide_counter`.
+#[cfg(test)]
+impl_counter!(u32);
@@ -0,0 +1,279 @@
+//! Iterator adaptors which choose a random element from each run of consecutive equal elements.
+//!
+//! Created by [`Kindness::choose_dedup`](crate::Kindness::choose_dedup),
+//! [`Kindness::choose_dedup_by`](crate::Kindness::choose_dedup_by) and
+//! [`Kindness::choose_dedup_by_key`](crate::Kindness::choose_dedup_by_key).
+
+use core::fmt;
+
+use rand::RngCore;
+
+use crate::coin_flipper::CoinFlipper;
+
+/// Decides whether an element belongs to the current run.
+pub trait SameRun<T> {
+    /// Called with the first element of the iterator.
+    fn start(&mut self, first: &T);
+
+    /// Returns `true` if `item` belongs to the same run as `representative`.
+    /// If not, `item` starts a new run.
+    fn same(&mut self, representative: &T, item: &T) -> bool;
+}
+
+/// Compares elements with [`PartialEq`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ByEq;
+
+impl<T: PartialEq> SameRun<T> for ByEq {
+    fn start(&mut self, _first: &T) {}
+
+    #[inline]
+    fn same(&mut self, representative: &T, item: &T) -> bool {
+        representative == item
+    }
+}
+
+/// Compares elements with a function.
+#[derive(Clone)]
+pub struct ByFn<F>(F);
+
+impl<F> fmt::Debug for ByFn<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ByFn").finish_non_exhaustive()
+    }
+}
+
+impl<T, F: FnMut(&T, &T) -> bool> SameRun<T> for ByFn<F> {
+    fn start(&mut self, _first: &T) {}
+
+    #[inline]
+    fn same(&mut self, representative: &T, item: &T) -> bool {
+        (self.0)(representative, item)
+    }
+}
+
+/// Compares the keys of elements.
+/// Keeps the key of the current run so the key function is called exactly once for each element.
+#[derive(Clone)]
+pub struct ByKey<F, K> {
+    f: F,
+    key: Option<K>,
+}
+
+impl<F, K: fmt::Debug> fmt::Debug for ByKey<F, K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ByKey")
+            .field("key", &self.key)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<T, K: PartialEq, F: FnMut(&T) -> K> SameRun<T> for ByKey<F, K> {
+    fn start(&mut self, first: &T) {
+        self.key = Some((self.f)(first));
+    }
+
+    #[inline]
+    fn same(&mut self, _representative: &T, item: &T) -> bool {
+        let key = (self.f)(item);
+        if self.key.as_ref() == Some(&key) {
+            true
+        } else {
+            self.key = Some(key);
+            false
+        }
+    }
+}
+
+/// An iterator which returns a uniformly random element from each run of consecutive elements which are the same
+/// according to `P`.
+///
+/// Lazy and uses constant memory: each call to [`Iterator::next`] consumes one run and the first element of the next.
+#[must_use = "iterator adaptors are lazy and do nothing unless consumed"]
+pub struct DedupRuns<I: Iterator, R: RngCore, P> {
+    iterator: I,
+    coin_flipper: CoinFlipper<R>,
+    same_run: P,
+    /// The first element of the next run
+    pending: Option<I::Item>,
+}
+
+/// Created by [`Kindness::choose_dedup`](crate::Kindness::choose_dedup).
+pub type Dedup<I, R> = DedupRuns<I, R, ByEq>;
+
+/// Created by [`Kindness::choose_dedup_by`](crate::Kindness::choose_dedup_by).
+pub type DedupBy<I, R, F> = DedupRuns<I, R, ByFn<F>>;
+
+/// Created by [`Kindness::choose_dedup_by_key`](crate::Kindness::choose_dedup_by_key).
+pub type DedupByKey<I, R, F, K> = DedupRuns<I, R, ByKey<F, K>>;
+
+impl<I: Iterator, R: RngCore, P> DedupRuns<I, R, P> {
+    fn new(iterator: I, rng: R, same_run: P) -> Self {
+        Self {
+            iterator,
+            coin_flipper: CoinFlipper::new(rng),
+            same_run,
+            pending: None,
+        }
+    }
+}
+
+impl<I: Iterator, R: RngCore> Dedup<I, R> {
+    pub(crate) fn by_eq(iterator: I, rng: R) -> Self {
+        Self::new(iterator, rng, ByEq)
+    }
+}
+
+impl<I: Iterator, R: RngCore, F> DedupBy<I, R, F> {
+    pub(crate) fn by_fn(iterator: I, rng: R, same_bucket: F) -> Self {
+        Self::new(iterator, rng, ByFn(same_bucket))
+    }
+}
+
+impl<I: Iterator, R: RngCore, F, K> DedupByKey<I, R, F, K> {
+    pub(crate) fn by_key(iterator: I, rng: R, key: F) -> Self {
+        Self::new(iterator, rng, ByKey { f: key, key: None })
+    }
+}
+
+impl<I: Iterator, R: RngCore, P: SameRun<I::Item>> Iterator for DedupRuns<I, R, P> {
+    type Item = I::Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut chosen = match self.pending.take() {
+            Some(item) => item,
+            None => {
+                let first = self.iterator.next()?;
+                self.same_run.start(&first);
+                first
+            }
+        };
+        let mut consumed: usize = 1;
+
+        for item in self.iterator.by_ref() {
+            if !self.same_run.same(&chosen, &item) {
+                self.pending = Some(item);
+                break;
+            }
+            consumed = consumed.saturating_add(1);
+            //Choose either item or chosen randomly, see random_element for more
+            if self.coin_flipper.gen_ratio_one_over(consumed) {
+                chosen = item;
+            }
+        }
+        Some(chosen)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let pending = usize::from(self.pending.is_some());
+        let (lower, upper) = self.iterator.size_hint();
+        (
+            pending.max(lower.min(1)),
+            upper.and_then(|upper| upper.checked_add(pending)),
+        )
+    }
+}
+
+impl<I: Iterator + fmt::Debug, R: RngCore, P: fmt::Debug> fmt::Debug for DedupRuns<I, R, P>
+where
+    I::Item: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DedupRuns")
+            .field("iterator", &self.iterator)
+            .field("same_run", &self.same_run)
+            .field("pending", &self.pending)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use crate::Kindness;
+
+    const RUNS: usize = 10000;
+    const LENGTH: usize = 100;
+    const LOWER_TOLERANCE: usize = 60;
+    const UPPER_TOLERANCE: usize = 140;
+
+    #[test]
+    fn test_choose_dedup() {
+        let mut rng = StdRng::seed_from_u64(123);
+        let deduped: Vec<_> = [1, 1, 2, 2, 2, 1, 3, 3]
+            .into_iter()
+            .choose_dedup(&mut rng)
+            .collect();
+        assert_eq!(deduped, [1, 2, 1, 3]);
+        assert_eq!((0..0).choose_dedup(&mut rng).next(), None);
+    }
+
+    #[test]
+    fn test_choose_dedup_by_key() {
+        let mut counts: [usize; LENGTH] = [0; LENGTH];
+        let mut rng = StdRng::seed_from_u64(123);
+
+        for _ in 0..RUNS {
+            let mut runs = 0;
+            for x in (0..LENGTH).choose_dedup_by_key(&mut rng, |x| x / 10) {
+                assert_eq!(x / 10, runs);
+                runs += 1;
+                counts[x] += 1;
+            }
+            assert_eq!(runs, 10);
+        }
+
+        insta::assert_debug_snapshot!(counts);
+        for x in counts {
+            assert!(x > LOWER_TOLERANCE * 10);
+            assert!(x < UPPER_TOLERANCE * 10);
+        }
+    }
+
+    #[test]
+    fn test_choose_dedup_by() {
+        let mut counts: [usize; LENGTH] = [0; LENGTH];
+        let mut rng = StdRng::seed_from_u64(123);
+
+        for _ in 0..RUNS {
+            for x in (0..LENGTH).choose_dedup_by(&mut rng, |a, b| a / 10 == b / 10) {
+                counts[x] += 1;
+            }
+        }
+
+        for x in counts {
+            assert!(x > LOWER_TOLERANCE * 10);
+            assert!(x < UPPER_TOLERANCE * 10);
+        }
+    }
+
+    #[test]
+    fn test_choose_dedup_is_lazy() {
+        let mut rng = StdRng::seed_from_u64(123);
+        let mut consumed = 0;
+        let mut key_calls = 0;
+        let first = (0..LENGTH)
+            .inspect(|_| consumed += 1)
+            .choose_dedup_by_key(&mut rng, |x| {
+                key_calls += 1;
+                x / 10
+            })
+            .next()
+            .unwrap();
+        assert!(first < 10);
+        // One run and the first element of the next
+        assert_eq!(consumed, 11);
+        assert_eq!(key_calls, 11);
+    }
+
+    #[test]
+    fn test_choose_dedup_size_hint() {
+        let mut rng = StdRng::seed_from_u64(123);
+        let mut dedup = (0..LENGTH).choose_dedup_by_key(&mut rng, |x| x / 10);
+        assert_eq!(dedup.size_hint(), (1, Some(LENGTH)));
+        dedup.next();
+        assert_eq!(dedup.size_hint(), (1, Some(LENGTH - 10)));
+        assert_eq!(dedup.count(), 9);
+    }
+}
@@ -0,0 +1,292 @@
+use alloc::vec::Vec;
+
+use rand::Rng;
+
+use crate::coin_flipper::CoinFlipper;
+
+/// A collection of weighted elements which supports drawing a random element with probability proportional to its weight,
+/// as well as changing weights, inserting and removing elements, all in `O(log n)` time.
+///
+/// Each element is identified by a key which is returned when it is inserted and stays the same until it is removed.
+/// Keys of removed elements are reused.
+///
+/// Created with [`DynamicWeightedIndex::new`] or [`Kindness::collect_dynamic_weighted`](crate::Kindness::collect_dynamic_weighted).
+#[derive(Debug, Clone)]
+pub struct DynamicWeightedIndex<T> {
+    items: Vec<Option<T>>,
+    weights: Vec<u64>,
+    /// A Fenwick tree of the weights: `tree[i - 1]` is the sum of the weights of `(i - lowbit(i), i]`
+    tree: Vec<u128>,
+    free: Vec<usize>,
+    total: u128,
+}
+
+impl<T> Default for DynamicWeightedIndex<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[inline]
+const fn lowbit(i: usize) -> usize {
+    i & i.wrapping_neg()
+}
+
+impl<T> DynamicWeightedIndex<T> {
+    /// Creates an empty index.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            items: Vec::new(),
+            weights: Vec::new(),
+            tree: Vec::new(),
+            free: Vec::new(),
+            total: 0,
+        }
+    }
+
+    pub(crate) fn from_iter<I: Iterator<Item = T>, F: FnMut(&T) -> u64>(
+        iterator: I,
+        mut weight: F,
+    ) -> Self {
+        let mut index = Self::new();
+        for item in iterator {
+            let w = weight(&item);
+            index.items.push(Some(item));
+            index.weights.push(w);
+            index.tree.push(u128::from(w));
+            index.total += u128::from(w);
+        }
+        // Build the tree in linear time by adding each node to its parent
+        for i in 1..=index.tree.len() {
+            let parent = i + lowbit(i);
+            if parent <= index.tree.len() {
+                index.tree[parent - 1] += index.tree[i - 1];
+            }
+        }
+        index
+    }
+
+    /// The number of elements.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.items.len() - self.free.len()
+    }
+
+    /// Returns `true` if there are no elements.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The sum of the weights of all elements.
+    #[must_use]
+    pub const fn total_weight(&self) -> u128 {
+        self.total
+    }
+
+    /// Returns the element with this key.
+    #[must_use]
+    pub fn get(&self, key: usize) -> Option<&T> {
+        self.items.get(key)?.as_ref()
+    }
+
+    /// Returns the element with this key.
+    #[must_use]
+    pub fn get_mut(&mut self, key: usize) -> Option<&mut T> {
+        self.items.get_mut(key)?.as_mut()
+    }
+
+    /// Returns the weight of the element with this key.
+    #[must_use]
+    pub fn weight(&self, key: usize) -> Option<u64> {
+        self.get(key)?;
+        Some(self.weights[key])
+    }
+
+    /// Adds an element with the given weight and returns its key.
+    pub fn insert(&mut self, item: T, weight: u64) -> usize {
+        if let Some(key) = self.free.pop() {
+            self.items[key] = Some(item);
+            self.set_weight(key, weight);
+            return key;
+        }
+
+        let key = self.items.len();
+        let i = key + 1;
+        // The new node covers `(i - lowbit(i), i]`, which is its own weight plus some existing nodes
+        let node = u128::from(weight) + self.prefix_sum(i - 1) - self.prefix_sum(i - lowbit(i));
+        self.items.push(Some(item));
+        self.weights.push(weight);
+        self.tree.push(node);
+        self.total += u128::from(weight);
+        key
+    }
+
+    /// Changes the weight of the element with this key.
+    /// Returns the previous weight, or [`None`] if there is no element with this key.
+    pub fn update_weight(&mut self, key: usize, weight: u64) -> Option<u64> {
+        let previous = self.weight(key)?;
+        self.set_weight(key, weight);
+        Some(previous)
+    }
+
+    /// Removes the element with this key and returns it.
+    /// Returns [`None`] if there is no element with this key.
+    pub fn remove(&mut self, key: usize) -> Option<T> {
+        let item = self.items.get_mut(key)?.take()?;
+        self.set_weight(key, 0);
+        self.free.push(key);
+        Some(item)
+    }
+
+    /// Returns the key of a random element, chosen with probability proportional to its weight.
+    /// Returns [`None`] if the total weight is zero.
+    ///
+    /// This descends the tree, going left with probability exactly equal to the weight of the left subtree
+    /// divided by the weight of the whole subtree, so it needs an expected two random bits per level.
+    pub fn sample<R: Rng>(&self, rng: &mut R) -> Option<usize> {
+        if self.total == 0 {
+            return None;
+        }
+        let mut coin_flipper = CoinFlipper::new(rng);
+        let n = self.tree.len();
+        let mut pos = 0;
+        // The weight of `(pos, pos + 2 * step]`
+        let mut mass = self.total;
+        let mut step = n.next_power_of_two() / 2;
+
+        while step > 0 {
+            if pos + step <= n {
+                let left = self.tree[pos + step - 1];
+                if left == 0 || (left < mass && !coin_flipper.gen_ratio(left, mass)) {
+                    pos += step;
+                    mass -= left;
+                } else {
+                    mass = left;
+                }
+            }
+            step /= 2;
+        }
+        Some(pos)
+    }
+
+    fn set_weight(&mut self, key: usize, weight: u64) {
+        let delta = u128::from(weight).wrapping_sub(u128::from(self.weights[key]));
+        self.weights[key] = weight;
+        self.total = self.total.wrapping_add(delta);
+        let mut i = key + 1;
+        while i <= self.tree.len() {
+            self.tree[i - 1] = self.tree[i - 1].wrapping_add(delta);
+            i += lowbit(i);
+        }
+    }
+
+    /// The sum of the weights of `[0, i)`
+    fn prefix_sum(&self, mut i: usize) -> u128 {
+        let mut sum = 0;
+        while i > 0 {
+            sum += self.tree[i - 1];
+            i -= lowbit(i);
+        }
+        sum
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use super::DynamicWeightedIndex;
+    use crate::Kindness;
+
+    const RUNS: usize = 100000;
+
+    fn assert_distribution<T>(index: &DynamicWeightedIndex<T>, expected: &[u64]) {
+        let mut counts = vec![0usize; expected.len()];
+        let mut rng = StdRng::seed_from_u64(123);
+        for _ in 0..RUNS {
+            counts[index.sample(&mut rng).unwrap()] += 1;
+        }
+        let total: u64 = expected.iter().sum();
+        for (key, (count, weight)) in counts.into_iter().zip(expected).enumerate() {
+            let expected = RUNS * (*weight as usize) / (total as usize);
+            if expected == 0 {
+                assert_eq!(count, 0, "{key}");
+            } else {
+                assert!(count * 10 > expected * 9, "{key}: {count} vs {expected}");
+                assert!(count * 10 < expected * 11, "{key}: {count} vs {expected}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_dynamic_weighted_empty() {
+        let mut rng = StdRng::seed_from_u64(123);
+        let mut index = DynamicWeightedIndex::new();
+        assert_eq!(index.sample(&mut rng), None);
+        index.insert("a", 0);
+        assert_eq!(index.sample(&mut rng), None);
+    }
+
+    #[test]
+    fn test_dynamic_weighted_collect() {
+        let index = (0..10u64).collect_dynamic_weighted(|x| x + 1);
+        assert_eq!(index.len(), 10);
+        assert_eq!(index.total_weight(), 55);
+        assert_eq!(index.get(3), Some(&3));
+        assert_distribution(&index, &[1, 2, 3, 4, 5, 6, 7, 8, 9, 10]);
+    }
+
+    #[test]
+    fn test_dynamic_weighted_insert_matches_collect() {
+        let collected = (0..37u64).collect_dynamic_weighted(|x| x * x);
+        let mut inserted = DynamicWeightedIndex::new();
+        for x in 0..37u64 {
+            assert_eq!(inserted.insert(x, x * x), x as usize);
+        }
+        assert_eq!(collected.tree, inserted.tree);
+    }
+
+    #[test]
+    fn test_dynamic_weighted_update_and_remove() {
+        let mut index = (0..7u64).collect_dynamic_weighted(|_| 1);
+        assert_eq!(index.update_weight(2, 10), Some(1));
+        assert_eq!(index.update_weight(7, 10), None);
+        assert_eq!(index.remove(5), Some(5));
+        assert_eq!(index.remove(5), None);
+        assert_eq!(index.weight(5), None);
+        assert_eq!(index.len(), 6);
+        assert_distribution(&index, &[1, 1, 10, 1, 1, 0, 1]);
+
+        assert_eq!(index.insert(100, 3), 5);
+        assert_eq!(index.get(5), Some(&100));
+        assert_eq!(index.insert(101, 4), 7);
+        assert_distribution(&index, &[1, 1, 10, 1, 1, 3, 1, 4]);
+    }
+
+    #[test]
+    fn test_dynamic_weighted_snapshot() {
+        let mut index = (0..10u64).collect_dynamic_weighted(|x| x + 1);
+        index.update_weight(0, 20);
+        index.remove(9);
+        let mut counts = [0usize; 10];
+        let mut rng = StdRng::seed_from_u64(123);
+        for _ in 0..RUNS {
+            counts[index.sample(&mut rng).unwrap()] += 1;
+        }
+        insta::assert_debug_snapshot!(counts);
+    }
+
+    #[test]
+    fn test_dynamic_weighted_extreme_weights() {
+        // The total weight does not fit in a u64, and index 1 has about 1 / 2001 of it
+        let small = u64::MAX / 1000;
+        let index = [u64::MAX, small, u64::MAX].into_iter().collect_dynamic_weighted(|x| *x);
+        assert_eq!(index.total_weight(), 2 * u128::from(u64::MAX) + u128::from(small));
+        let mut rng = StdRng::seed_from_u64(123);
+        let smalls = (0..RUNS * 10).filter(|_| index.sample(&mut rng) == Some(1)).count();
+        assert!(smalls > 400);
+        assert!(smalls < 600);
+    }
+}
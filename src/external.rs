@@ -0,0 +1,494 @@
+//! Shuffling data sets which are larger than memory by spilling them to temporary files.
+
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::num::NonZeroU32;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use rand::RngCore;
+
+use crate::index::gen_index_inclusive;
+use crate::uniform::{FastDiceRoller, Uniform};
+
+/// Writes records to bucket files and reads them back.
+pub trait Serializer<T> {
+    /// Writes one record.
+    fn serialize<W: Write>(&self, record: &T, writer: &mut W) -> io::Result<()>;
+
+    /// Reads the next record, or returns [`None`] at the end of the file.
+    fn deserialize<B: BufRead>(&self, reader: &mut B) -> io::Result<Option<T>>;
+}
+
+/// Serializes [`String`]s as lines, for line-oriented data sets.
+///
+/// Records must not contain `'\n'`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Lines;
+
+impl Serializer<String> for Lines {
+    fn serialize<W: Write>(&self, record: &String, writer: &mut W) -> io::Result<()> {
+        if record.contains('\n') {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Records cannot contain newlines",
+            ));
+        }
+        writer.write_all(record.as_bytes())?;
+        writer.write_all(b"\n")
+    }
+
+    fn deserialize<B: BufRead>(&self, reader: &mut B) -> io::Result<Option<String>> {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+        // The newline written by `serialize`
+        line.pop();
+        Ok(Some(line))
+    }
+}
+
+/// Limits on the resources used by [`external_shuffle_with_limits`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Limits {
+    /// The largest bucket, in serialized bytes, which is shuffled in memory.
+    /// Larger buckets are spread across more bucket files until they fit.
+    pub memory: u64,
+    /// The number of bucket files records are spread across at once, all of which are open while spreading.
+    /// Must be at least 2.
+    pub buckets: usize,
+}
+
+impl Default for Limits {
+    /// 64 MiB of records in memory and 64 bucket files.
+    fn default() -> Self {
+        Self {
+            memory: 64 << 20,
+            buckets: 64,
+        }
+    }
+}
+
+/// Distinguishes the spill directories of shuffles in the same process
+static NEXT_SPILL_DIR: AtomicUsize = AtomicUsize::new(0);
+
+/// A bucket file which has not been read yet
+#[derive(Debug)]
+struct Bucket {
+    path: PathBuf,
+    bytes: u64,
+    records: usize,
+}
+
+/// A writer which counts the bytes written
+struct Counting<W> {
+    inner: W,
+    bytes: u64,
+}
+
+impl<W: Write> Write for Counting<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.bytes += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Owns the spill directory, which is removed on drop, and spreads records across bucket files in it.
+struct Spiller<R> {
+    dir: PathBuf,
+    next_file: usize,
+    rng: R,
+    /// Chooses buckets
+    uniform: Uniform,
+    /// Chooses records from the current bucket
+    roller: FastDiceRoller,
+    limits: Limits,
+}
+
+impl<R> Spiller<R> {
+    fn new(spill_dir: &Path, rng: R, limits: Limits) -> io::Result<Self> {
+        let buckets = u32::try_from(limits.buckets)
+            .ok()
+            .filter(|&buckets| buckets >= 2)
+            .and_then(NonZeroU32::new)
+            .expect("The number of buckets must be between 2 and u32::MAX");
+        let dir = loop {
+            let dir = spill_dir.join(format!(
+                "kindness-shuffle-{}-{}",
+                std::process::id(),
+                NEXT_SPILL_DIR.fetch_add(1, Ordering::Relaxed)
+            ));
+            match fs::create_dir(&dir) {
+                Ok(()) => break dir,
+                Err(error) if error.kind() == io::ErrorKind::AlreadyExists => {}
+                Err(error) => return Err(error),
+            }
+        };
+        Ok(Self {
+            dir,
+            next_file: 0,
+            rng,
+            uniform: Uniform::new(buckets),
+            roller: FastDiceRoller::new(NonZeroU32::MIN),
+            limits,
+        })
+    }
+}
+
+impl<R: RngCore> Spiller<R> {
+    /// Writes each record to a uniformly random new bucket file.
+    /// Returns the buckets which are not empty.
+    fn spread<T, S: Serializer<T>>(
+        &mut self,
+        serializer: &S,
+        records: impl Iterator<Item = io::Result<T>>,
+    ) -> io::Result<Vec<Bucket>> {
+        let mut writers = Vec::with_capacity(self.limits.buckets);
+        for _ in 0..self.limits.buckets {
+            let path = self.dir.join(self.next_file.to_string());
+            self.next_file += 1;
+            let inner = BufWriter::new(File::create_new(&path)?);
+            let bucket = Bucket {
+                path,
+                bytes: 0,
+                records: 0,
+            };
+            writers.push((Counting { inner, bytes: 0 }, bucket));
+        }
+
+        for record in records {
+            let record = record?;
+            let (writer, bucket) = &mut writers[self.uniform.next(&mut self.rng) as usize];
+            serializer.serialize(&record, writer)?;
+            bucket.records += 1;
+        }
+
+        let mut buckets = Vec::with_capacity(writers.len());
+        for (mut writer, mut bucket) in writers {
+            writer.flush()?;
+            if bucket.records == 0 {
+                fs::remove_file(&bucket.path)?;
+            } else {
+                bucket.bytes = writer.bytes;
+                buckets.push(bucket);
+            }
+        }
+        Ok(buckets)
+    }
+}
+
+impl<R> Drop for Spiller<R> {
+    fn drop(&mut self) {
+        // There is nowhere to report an error, and the files are temporary anyway
+        let _ = fs::remove_dir_all(&self.dir);
+    }
+}
+
+/// Shuffles the records of `iterator`, which may be larger than memory, using temporary files in `spill_dir`,
+/// with the default [`Limits`].
+///
+/// See [`external_shuffle_with_limits`].
+pub fn external_shuffle<T, R: RngCore, S: Serializer<T>>(
+    rng: R,
+    iterator: impl IntoIterator<Item = T>,
+    spill_dir: impl AsRef<Path>,
+    serializer: S,
+) -> io::Result<ExternalShuffle<T, R, S>> {
+    external_shuffle_with_limits(rng, iterator, spill_dir, serializer, Limits::default())
+}
+
+/// Shuffles the records of `iterator`, which may be larger than memory, using temporary files in `spill_dir`.
+///
+/// Every record is written with `serializer` to one of [`Limits::buckets`] bucket files, chosen uniformly at random,
+/// in a new directory inside `spill_dir`.
+/// The returned iterator then reads the buckets one at a time, shuffles each in memory and returns its records,
+/// so every permutation is equally likely.
+/// A bucket larger than [`Limits::memory`] is first spread across new bucket files in the same way.
+///
+/// The whole iterator is consumed before this returns.
+/// The temporary directory is removed when the returned iterator is dropped, or if this returns an error.
+///
+/// # Panics
+///
+/// Panics if [`Limits::buckets`] is less than 2 or more than `u32::MAX`.
+///
+/// ```
+/// use kindness::external::{external_shuffle_with_limits, Limits, Lines};
+/// use rand::SeedableRng;
+///
+/// let mut rng = rand::rngs::StdRng::seed_from_u64(123);
+/// let lines = (0..1000).map(|x| x.to_string());
+/// let limits = Limits { memory: 1024, buckets: 4 };
+/// let shuffled = external_shuffle_with_limits(&mut rng, lines, std::env::temp_dir(), Lines, limits)?;
+/// let shuffled: Vec<String> = shuffled.collect::<std::io::Result<_>>()?;
+/// assert_eq!(shuffled.len(), 1000);
+/// # Ok::<(), std::io::Error>(())
+/// ```
+pub fn external_shuffle_with_limits<T, R: RngCore, S: Serializer<T>>(
+    rng: R,
+    iterator: impl IntoIterator<Item = T>,
+    spill_dir: impl AsRef<Path>,
+    serializer: S,
+    limits: Limits,
+) -> io::Result<ExternalShuffle<T, R, S>> {
+    let mut spiller = Spiller::new(spill_dir.as_ref(), rng, limits)?;
+    let buckets = spiller.spread(&serializer, iterator.into_iter().map(Ok))?;
+    Ok(ExternalShuffle {
+        remaining: buckets.iter().map(|bucket| bucket.records).sum(),
+        // The first bucket is read first
+        pending: buckets.into_iter().rev().collect(),
+        current: Vec::new(),
+        spiller,
+        serializer,
+        failed: false,
+    })
+}
+
+/// An iterator over the shuffled records of a data set which may be larger than memory.
+///
+/// Returns the first error reading a bucket, after which it returns [`None`].
+/// The temporary files are removed on drop.
+///
+/// Created by [`external_shuffle`] and [`external_shuffle_with_limits`].
+#[must_use = "iterators are lazy and do nothing unless consumed"]
+pub struct ExternalShuffle<T, R, S> {
+    spiller: Spiller<R>,
+    serializer: S,
+    /// The buckets which have not been read yet, the next last
+    pending: Vec<Bucket>,
+    /// The records of the current bucket which have not been returned yet
+    current: Vec<T>,
+    /// The number of records which have not been returned yet
+    remaining: usize,
+    failed: bool,
+}
+
+impl<T, R: RngCore, S: Serializer<T>> ExternalShuffle<T, R, S> {
+    fn advance(&mut self) -> io::Result<Option<T>> {
+        loop {
+            if let Some(inclusive_upper_bound) = self.current.len().checked_sub(1) {
+                let index = gen_index_inclusive(
+                    &mut self.spiller.roller,
+                    &mut self.spiller.rng,
+                    inclusive_upper_bound,
+                );
+                self.remaining -= 1;
+                return Ok(Some(self.current.swap_remove(index)));
+            }
+            let Some(bucket) = self.pending.pop() else {
+                return Ok(None);
+            };
+            {
+                let mut reader = BufReader::new(File::open(&bucket.path)?);
+                let serializer = &self.serializer;
+                let records =
+                    std::iter::from_fn(|| serializer.deserialize(&mut reader).transpose());
+                if bucket.bytes <= self.spiller.limits.memory || bucket.records <= 1 {
+                    self.current = records.collect::<io::Result<_>>()?;
+                } else {
+                    let buckets = self.spiller.spread(serializer, records)?;
+                    self.pending.extend(buckets.into_iter().rev());
+                }
+            }
+            fs::remove_file(&bucket.path)?;
+        }
+    }
+}
+
+impl<T, R: RngCore, S: Serializer<T>> Iterator for ExternalShuffle<T, R, S> {
+    type Item = io::Result<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+        match self.advance() {
+            Ok(record) => record.map(Ok),
+            Err(error) => {
+                self.failed = true;
+                Some(Err(error))
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        if self.failed {
+            (0, Some(0))
+        } else {
+            // An error ends the iteration early
+            (0, Some(self.remaining))
+        }
+    }
+}
+
+impl<T, R, S> std::fmt::Debug for ExternalShuffle<T, R, S> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ExternalShuffle")
+            .field("dir", &self.spiller.dir)
+            .field("pending", &self.pending)
+            .field("remaining", &self.remaining)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{self, BufRead, Write};
+    use std::path::PathBuf;
+
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use super::{external_shuffle, external_shuffle_with_limits, Limits, Lines, Serializer};
+
+    const RUNS: usize = 1000;
+    const LENGTH: usize = 10;
+    const LOWER_TOLERANCE: usize = 60;
+    const UPPER_TOLERANCE: usize = 140;
+
+    /// Serializes `u32`s as four bytes
+    struct LittleEndian;
+
+    impl Serializer<u32> for LittleEndian {
+        fn serialize<W: Write>(&self, record: &u32, writer: &mut W) -> io::Result<()> {
+            writer.write_all(&record.to_le_bytes())
+        }
+
+        fn deserialize<B: BufRead>(&self, reader: &mut B) -> io::Result<Option<u32>> {
+            if reader.fill_buf()?.is_empty() {
+                return Ok(None);
+            }
+            let mut bytes = [0; 4];
+            reader.read_exact(&mut bytes)?;
+            Ok(Some(u32::from_le_bytes(bytes)))
+        }
+    }
+
+    /// A new empty directory, which is removed when dropped
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(name: &str) -> Self {
+            let path =
+                std::env::temp_dir().join(format!("kindness-test-{}-{name}", std::process::id()));
+            let _ = std::fs::remove_dir_all(&path);
+            std::fs::create_dir_all(&path).unwrap();
+            Self(path)
+        }
+
+        fn is_empty(&self) -> bool {
+            std::fs::read_dir(&self.0).unwrap().next().is_none()
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[test]
+    fn test_external_shuffle_is_permutation() {
+        let dir = TempDir::new("permutation");
+        let mut rng = StdRng::seed_from_u64(123);
+        // Small enough that buckets are spread again, several times
+        let limits = Limits {
+            memory: 64,
+            buckets: 3,
+        };
+        let shuffled =
+            external_shuffle_with_limits(&mut rng, 0..1000, &dir.0, LittleEndian, limits).unwrap();
+        assert_eq!(shuffled.size_hint(), (0, Some(1000)));
+        let mut shuffled: Vec<u32> = shuffled.collect::<io::Result<_>>().unwrap();
+        assert_ne!(shuffled, (0..1000).collect::<Vec<_>>());
+        shuffled.sort_unstable();
+        assert!(shuffled.into_iter().eq(0..1000));
+        assert!(dir.is_empty());
+    }
+
+    #[test]
+    fn test_external_shuffle_is_uniform() {
+        let dir = TempDir::new("uniform");
+        let mut counts: [usize; LENGTH] = [0; LENGTH];
+        let mut rng = StdRng::seed_from_u64(123);
+        let limits = Limits {
+            memory: 8,
+            buckets: 2,
+        };
+
+        for _ in 0..RUNS {
+            let first = external_shuffle_with_limits(
+                &mut rng,
+                0..LENGTH as u32,
+                &dir.0,
+                LittleEndian,
+                limits,
+            )
+            .unwrap()
+            .next()
+            .unwrap()
+            .unwrap();
+            counts[first as usize] += 1;
+        }
+
+        insta::assert_debug_snapshot!(counts);
+        for x in counts {
+            assert!(x > LOWER_TOLERANCE);
+            assert!(x < UPPER_TOLERANCE);
+        }
+    }
+
+    #[test]
+    fn test_external_shuffle_lines() {
+        let dir = TempDir::new("lines");
+        let mut rng = StdRng::seed_from_u64(123);
+        let lines: Vec<String> = (0..100).map(|x| format!("line {x}")).collect();
+        let mut shuffled: Vec<String> = external_shuffle(&mut rng, lines.clone(), &dir.0, Lines)
+            .unwrap()
+            .collect::<io::Result<_>>()
+            .unwrap();
+        shuffled.sort_unstable_by_key(|line| line[5..].parse::<usize>().unwrap());
+        assert_eq!(shuffled, lines);
+    }
+
+    #[test]
+    fn test_external_shuffle_cleans_up_on_drop() {
+        let dir = TempDir::new("drop");
+        let mut rng = StdRng::seed_from_u64(123);
+        let limits = Limits {
+            memory: 64,
+            buckets: 4,
+        };
+        let mut shuffled =
+            external_shuffle_with_limits(&mut rng, 0..1000, &dir.0, LittleEndian, limits).unwrap();
+        assert!(!dir.is_empty());
+        shuffled.next().unwrap().unwrap();
+        drop(shuffled);
+        assert!(dir.is_empty());
+    }
+
+    #[test]
+    fn test_external_shuffle_serialize_error() {
+        let dir = TempDir::new("error");
+        let mut rng = StdRng::seed_from_u64(123);
+        let lines = ["one".to_string(), "two\nthree".to_string()];
+        let error = external_shuffle(&mut rng, lines, &dir.0, Lines).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+        assert!(dir.is_empty());
+    }
+
+    #[test]
+    fn test_external_shuffle_empty() {
+        let dir = TempDir::new("empty");
+        let mut rng = StdRng::seed_from_u64(123);
+        let mut shuffled = external_shuffle(&mut rng, 0..0, &dir.0, LittleEndian).unwrap();
+        assert!(shuffled.next().is_none());
+        drop(shuffled);
+        assert!(dir.is_empty());
+    }
+}
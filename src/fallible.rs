@@ -0,0 +1,205 @@
+/// An iterator over the [`Ok`] values of an iterator of [`Result`]s which stops at the first error.
+///
+/// The size hint of the inner iterator is forwarded unchanged so that methods which depend on it
+/// consume the same random numbers as they would for the [`Ok`] values alone.
+pub(crate) struct Shunt<I, E> {
+    iterator: I,
+    error: Option<E>,
+}
+
+impl<T, E, I: Iterator<Item = Result<T, E>>> Iterator for Shunt<I, E> {
+    type Item = T;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.error.is_some() {
+            return None;
+        }
+        match self.iterator.next()? {
+            Ok(item) => Some(item),
+            Err(error) => {
+                self.error = Some(error);
+                None
+            }
+        }
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        if self.error.is_some() {
+            (0, Some(0))
+        } else {
+            self.iterator.size_hint()
+        }
+    }
+}
+
+/// Calls `f` with the [`Ok`] values of `iterator` and returns its result, or the first error.
+///
+/// If `f` does not consume the whole iterator, the rest is checked for errors.
+pub(crate) fn try_with<T, E, I: Iterator<Item = Result<T, E>>, X>(
+    iterator: I,
+    f: impl FnOnce(&mut Shunt<I, E>) -> X,
+) -> Result<X, E> {
+    let mut shunt = Shunt {
+        iterator,
+        error: None,
+    };
+    let result = f(&mut shunt);
+    shunt.by_ref().for_each(drop);
+    match shunt.error {
+        Some(error) => Err(error),
+        None => Ok(result),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::cell::Cell;
+
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use crate::Kindness;
+
+    const LENGTH: usize = 100;
+
+    fn oks() -> impl Iterator<Item = Result<usize, &'static str>> {
+        (0..LENGTH).map(Ok)
+    }
+
+    fn with_error(position: usize) -> impl Iterator<Item = Result<usize, usize>> {
+        (0..LENGTH).map(move |x| if x >= position { Err(x) } else { Ok(x) })
+    }
+
+    #[test]
+    fn test_try_matches_infallible() {
+        for seed in 0..32 {
+            let mut expected = StdRng::seed_from_u64(seed);
+            let mut actual = StdRng::seed_from_u64(seed);
+
+            assert_eq!(
+                oks().try_choose_item(&mut actual),
+                Ok((0..LENGTH).choose_item(&mut expected))
+            );
+            assert_eq!(
+                oks().filter(|_| true).try_choose_item(&mut actual),
+                Ok((0..LENGTH).filter(|_| true).choose_item(&mut expected))
+            );
+            assert_eq!(
+                oks().try_choose_max_by_key(&mut actual, |x| Ok(x / 10)),
+                Ok((0..LENGTH).choose_max_by_key(&mut expected, |x| x / 10))
+            );
+            assert_eq!(
+                oks().try_choose_min_by_key(&mut actual, |x| Ok(x / 10)),
+                Ok((0..LENGTH).choose_min_by_key(&mut expected, |x| x / 10))
+            );
+            assert_eq!(
+                oks().try_choose_max_by(&mut actual, |a, b| (a / 10).cmp(&(b / 10))),
+                Ok((0..LENGTH).choose_max_by(&mut expected, |a, b| (a / 10).cmp(&(b / 10))))
+            );
+            assert_eq!(
+                oks().try_choose_min_by(&mut actual, |a, b| (a / 10).cmp(&(b / 10))),
+                Ok((0..LENGTH).choose_min_by(&mut expected, |a, b| (a / 10).cmp(&(b / 10))))
+            );
+            assert_eq!(
+                oks().map(|x| x.map(|x| x % 3)).try_choose_max(&mut actual),
+                Ok((0..LENGTH).map(|x| x % 3).choose_max(&mut expected))
+            );
+            assert_eq!(
+                oks().map(|x| x.map(|x| x % 3)).try_choose_min(&mut actual),
+                Ok((0..LENGTH).map(|x| x % 3).choose_min(&mut expected))
+            );
+
+            let hash_builder =
+                || core::hash::BuildHasherDefault::<std::hash::DefaultHasher>::default();
+            let mut expected_unique: Vec<_> = (0..LENGTH)
+                .choose_unique_by_key_with_hasher_in(
+                    &mut expected,
+                    |x| x % 10,
+                    hash_builder(),
+                    allocator_api2::alloc::Global,
+                )
+                .collect();
+            let mut actual_unique: Vec<_> = oks()
+                .try_choose_unique_by_key_with_hasher_in(
+                    &mut actual,
+                    |x| Ok(x % 10),
+                    hash_builder(),
+                    allocator_api2::alloc::Global,
+                )
+                .unwrap()
+                .collect();
+            expected_unique.sort_unstable();
+            actual_unique.sort_unstable();
+            assert_eq!(actual_unique, expected_unique);
+        }
+    }
+
+    #[test]
+    fn test_try_returns_first_error() {
+        let mut rng = StdRng::seed_from_u64(123);
+        for position in [0, 1, 50, LENGTH - 1] {
+            // An exact size hint means `choose_item` skips elements, but they are still checked
+            assert_eq!(
+                with_error(position).try_choose_item(&mut rng),
+                Err(position)
+            );
+            assert_eq!(with_error(position).try_choose_max(&mut rng), Err(position));
+            assert_eq!(with_error(position).try_choose_min(&mut rng), Err(position));
+            assert_eq!(
+                with_error(position).try_choose_max_by_key(&mut rng, |x| Ok(*x)),
+                Err(position)
+            );
+            assert_eq!(
+                with_error(position)
+                    .try_choose_unique_by_key(&mut rng, |x| Ok(*x))
+                    .map(|_| ()),
+                Err(position)
+            );
+        }
+    }
+
+    #[test]
+    fn test_try_key_error() {
+        let mut rng = StdRng::seed_from_u64(123);
+        let calls = Cell::new(0);
+        let result = oks().try_choose_min_by_key(&mut rng, |x| {
+            calls.set(calls.get() + 1);
+            if *x == 10 {
+                Err("ten")
+            } else {
+                Ok(*x)
+            }
+        });
+        assert_eq!(result, Err("ten"));
+        // The key function is not called again after the first error
+        assert_eq!(calls.get(), 11);
+
+        let result =
+            oks()
+                .try_choose_unique_by_key(&mut rng, |x| if *x == 10 { Err("ten") } else { Ok(*x) });
+        assert_eq!(result.map(|_| ()), Err("ten"));
+    }
+
+    #[test]
+    fn test_try_stops_at_first_error() {
+        let mut rng = StdRng::seed_from_u64(123);
+        let mut consumed = 0;
+        let result = with_error(10)
+            .inspect(|_| consumed += 1)
+            .try_choose_max(&mut rng);
+        assert_eq!(result, Err(10));
+        assert_eq!(consumed, 11);
+    }
+
+    #[test]
+    fn test_try_empty() {
+        let mut rng = StdRng::seed_from_u64(123);
+        assert_eq!(oks().take(0).try_choose_item(&mut rng), Ok(None));
+        assert_eq!(oks().take(0).try_choose_max(&mut rng), Ok(None));
+        assert_eq!(
+            oks().take(0).try_choose_min_by_key(&mut rng, |x| Ok(*x)),
+            Ok(None)
+        );
+    }
+}
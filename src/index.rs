@@ -0,0 +1,215 @@
+use alloc::collections::BTreeSet;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::num::NonZeroU32;
+
+use rand::Rng;
+
+use crate::uniform::FastDiceRoller;
+
+/// Returns `k` distinct random indices in `0..n`, in no particular order.
+///
+/// Uses Floyd's algorithm, which makes exactly `k` random draws and never allocates more than `k` indices.
+/// Checking whether an index has already been chosen is linear in `k` so this is best for small `k`.
+/// For large `k`, use [`sample_distinct_sorted`] or, if `k` is a large fraction of `n`, [`sample_distinct_dense`].
+///
+/// Panics if `k > n`.
+pub fn sample_distinct<R: Rng>(rng: &mut R, n: usize, k: usize) -> Vec<usize> {
+    let mut indices = Vec::with_capacity(k);
+    floyd(rng, n, k, |index| {
+        if indices.contains(&index) {
+            false
+        } else {
+            indices.push(index);
+            true
+        }
+    });
+    indices
+}
+
+/// Returns `k` distinct random indices in `0..n`, in ascending order.
+///
+/// Gives the same indices as [`sample_distinct`] for the same random number generator,
+/// but checks whether an index has already been chosen with a binary search.
+///
+/// Panics if `k > n`.
+pub fn sample_distinct_sorted<R: Rng>(rng: &mut R, n: usize, k: usize) -> Vec<usize> {
+    let mut indices = Vec::with_capacity(k);
+    floyd(rng, n, k, |index| match indices.binary_search(&index) {
+        Ok(_) => false,
+        Err(position) => {
+            indices.insert(position, index);
+            true
+        }
+    });
+    indices
+}
+
+/// Returns `k` distinct random indices in `0..n`, in ascending order.
+///
+/// Gives the same indices as [`sample_distinct_sorted`] for the same random number generator,
+/// but keeps track of the chosen indices with a bitset of `n` bits.
+/// This is faster when `k` is a large fraction of `n`.
+///
+/// Panics if `k > n`.
+pub fn sample_distinct_dense<R: Rng>(rng: &mut R, n: usize, k: usize) -> Vec<usize> {
+    let mut bits: Vec<u64> = vec![0; n.div_ceil(64)];
+    floyd(rng, n, k, |index| {
+        let word = &mut bits[index / 64];
+        let mask = 1 << (index % 64);
+        let absent = *word & mask == 0;
+        *word |= mask;
+        absent
+    });
+
+    let mut indices = Vec::with_capacity(k);
+    for (w, mut word) in bits.into_iter().enumerate() {
+        while word != 0 {
+            indices.push(w * 64 + word.trailing_zeros() as usize);
+            word &= word - 1;
+        }
+    }
+    indices
+}
+
+/// Below this many indices, [`sample_distinct_sorted`] is faster than a tree despite its quadratic insertion.
+const SORTED_MAX: usize = 1024;
+
+/// Returns `k` distinct random indices in `0..n`, in ascending order.
+///
+/// Gives the same indices as [`sample_distinct_sorted`] but picks the method by `k` and `n`:
+/// the bitset of [`sample_distinct_dense`] when it is no larger than the result (`k >= n / 64`),
+/// [`sample_distinct_sorted`] for small `k` and otherwise a tree, so this takes `O(k log k + min(k, n / 64))` time.
+pub(crate) fn sample_distinct_ascending<R: Rng>(rng: &mut R, n: usize, k: usize) -> Vec<usize> {
+    if k >= n / 64 {
+        sample_distinct_dense(rng, n, k)
+    } else if k <= SORTED_MAX {
+        sample_distinct_sorted(rng, n, k)
+    } else {
+        let mut indices = BTreeSet::new();
+        floyd(rng, n, k, |index| indices.insert(index));
+        indices.into_iter().collect()
+    }
+}
+
+/// Floyd's algorithm.
+/// `insert` should add the index to the set and return `false` if it was already there.
+fn floyd<R: Rng>(rng: &mut R, n: usize, k: usize, mut insert: impl FnMut(usize) -> bool) {
+    assert!(k <= n, "Cannot choose {k} distinct indices from {n}");
+    let mut roller = FastDiceRoller::new(NonZeroU32::MIN);
+    for j in (n - k)..n {
+        // `j` is not in the set yet so it can always be inserted
+        let t = gen_index_inclusive(&mut roller, rng, j);
+        if !insert(t) {
+            insert(j);
+        }
+    }
+}
+
+// Sample a number uniformly between 0 and `inclusive_upper_bound`. Uses 32-bit sampling where
+// possible, primarily in order to produce the same output on 32-bit and 64-bit
+// platforms.
+#[inline]
+pub(crate) fn gen_index_inclusive<R: Rng>(
+    roller: &mut FastDiceRoller,
+    rng: &mut R,
+    inclusive_upper_bound: usize,
+) -> usize {
+    match u32::try_from(inclusive_upper_bound + 1)
+        .ok()
+        .and_then(NonZeroU32::new)
+    {
+        Some(ubound) => roller.next_below(ubound, rng) as usize,
+        None => rng.random_range(0..=inclusive_upper_bound),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use super::*;
+
+    const RUNS: usize = 10000;
+    const LENGTH: usize = 100;
+    const LOWER_TOLERANCE: usize = 60;
+    const UPPER_TOLERANCE: usize = 140;
+
+    #[test]
+    fn test_sample_distinct_variants_agree() {
+        for (n, k) in [(0, 0), (1, 1), (10, 0), (10, 3), (10, 10), (100, 37), (1000, 999)] {
+            let mut unsorted = sample_distinct(&mut StdRng::seed_from_u64(123), n, k);
+            let sorted = sample_distinct_sorted(&mut StdRng::seed_from_u64(123), n, k);
+            let dense = sample_distinct_dense(&mut StdRng::seed_from_u64(123), n, k);
+
+            assert_eq!(sorted.len(), k);
+            assert!(sorted.windows(2).all(|w| w[0] < w[1]));
+            assert!(sorted.iter().all(|x| *x < n));
+            assert_eq!(sorted, dense);
+            unsorted.sort_unstable();
+            assert_eq!(sorted, unsorted);
+        }
+    }
+
+    #[test]
+    fn test_sample_distinct_ascending_agrees() {
+        // Covers the dense, sorted and tree methods
+        for (n, k) in [
+            (0, 0),
+            (10, 3),
+            (1000, 999),
+            (100_000, 1000),
+            (100_000, 1500),
+            (1_000_000, 5000),
+        ] {
+            let ascending = sample_distinct_ascending(&mut StdRng::seed_from_u64(123), n, k);
+            let sorted = sample_distinct_sorted(&mut StdRng::seed_from_u64(123), n, k);
+            assert_eq!(ascending, sorted);
+        }
+    }
+
+    #[test]
+    fn test_sample_distinct_distribution() {
+        // Each index should be chosen with probability k / n
+        let mut counts: [usize; LENGTH] = [0; LENGTH];
+        let mut rng = StdRng::seed_from_u64(123);
+        for _ in 0..RUNS {
+            for index in sample_distinct(&mut rng, LENGTH, 10) {
+                counts[index] += 1;
+            }
+        }
+
+        insta::assert_debug_snapshot!(counts);
+        for x in counts {
+            assert!(x > LOWER_TOLERANCE * 10);
+            assert!(x < UPPER_TOLERANCE * 10);
+        }
+    }
+
+    #[test]
+    fn test_sample_distinct_pairs() {
+        // Every pair should be equally likely
+        let mut counts = [[0usize; 5]; 5];
+        let mut rng = StdRng::seed_from_u64(123);
+        for _ in 0..RUNS {
+            let pair = sample_distinct_sorted(&mut rng, 5, 2);
+            counts[pair[0]][pair[1]] += 1;
+        }
+        for a in 0..5 {
+            for b in 0..5 {
+                if a < b {
+                    assert!(counts[a][b] > RUNS / 10 * 9 / 10, "{a},{b}: {}", counts[a][b]);
+                    assert!(counts[a][b] < RUNS / 10 * 11 / 10, "{a},{b}: {}", counts[a][b]);
+                } else {
+                    assert_eq!(counts[a][b], 0);
+                }
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_sample_distinct_too_many() {
+        sample_distinct(&mut StdRng::seed_from_u64(123), 3, 4);
+    }
+}
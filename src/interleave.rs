@@ -0,0 +1,184 @@
+//! Randomly interleaving several iterators.
+
+use alloc::vec::Vec;
+use core::fmt;
+use core::num::NonZeroU32;
+
+use rand::RngCore;
+
+use crate::index::gen_index_inclusive;
+use crate::uniform::FastDiceRoller;
+
+/// Returns an iterator over the elements of all of `iterators`, in a random order which keeps the order of each iterator.
+///
+/// At each step the next element is taken from one of the iterators with probability proportional to its remaining length.
+/// If every iterator has a size hint which indicates an exact length,
+/// every interleaving is equally likely, as if the union had been shuffled and each iterator's elements then put back in order.
+///
+/// The remaining length of an iterator is taken to be the lower bound of its size hint, but at least one,
+/// so iterators without a size hint are chosen as if they had one element left until they return [`None`].
+/// Lengths are added with saturating arithmetic.
+///
+/// This is lazy and allocates a vector of the iterators.
+///
+/// ```
+/// use kindness::*;
+/// use rand::SeedableRng;
+///
+/// let mut rng = rand::rngs::StdRng::seed_from_u64(123);
+/// let merged: Vec<_> = interleave_random(&mut rng, [0..3, 10..12, 20..24]).collect();
+/// assert_eq!(merged.len(), 9);
+/// let from_first: Vec<_> = merged.iter().filter(|&&x| x < 10).collect();
+/// assert_eq!(from_first, [&0, &1, &2]);
+/// ```
+pub fn interleave_random<I: Iterator, R: RngCore>(
+    rng: R,
+    iterators: impl IntoIterator<Item = I>,
+) -> InterleaveRandom<I, R> {
+    InterleaveRandom {
+        iterators: iterators.into_iter().collect(),
+        rng,
+        roller: FastDiceRoller::new(NonZeroU32::MIN),
+    }
+}
+
+/// An iterator which randomly interleaves several iterators, keeping the order of each.
+///
+/// Created by [`interleave_random`].
+#[must_use = "iterator adaptors are lazy and do nothing unless consumed"]
+pub struct InterleaveRandom<I, R> {
+    /// The iterators which have not returned [`None`] yet
+    iterators: Vec<I>,
+    rng: R,
+    roller: FastDiceRoller,
+}
+
+/// The weight of an iterator which has not returned [`None`] yet
+fn remaining<I: Iterator>(iterator: &I) -> usize {
+    iterator.size_hint().0.max(1)
+}
+
+impl<I: Iterator, R: RngCore> Iterator for InterleaveRandom<I, R> {
+    type Item = I::Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let total = self.iterators.iter().fold(0_usize, |total, iterator| {
+                total.saturating_add(remaining(iterator))
+            });
+            let mut index = match total.checked_sub(1) {
+                Some(inclusive_upper_bound) => {
+                    gen_index_inclusive(&mut self.roller, &mut self.rng, inclusive_upper_bound)
+                }
+                None => return None,
+            };
+            let position = self
+                .iterators
+                .iter()
+                .position(|iterator| {
+                    let weight = remaining(iterator);
+                    if index < weight {
+                        true
+                    } else {
+                        index -= weight;
+                        false
+                    }
+                })
+                .expect("The index is less than the total weight");
+            match self.iterators[position].next() {
+                Some(item) => return Some(item),
+                // Keep the order of the other iterators so the choice only depends on their lengths
+                None => drop(self.iterators.remove(position)),
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iterators
+            .iter()
+            .fold((0, Some(0)), |(lower, upper), iterator| {
+                let (l, u) = iterator.size_hint();
+                (
+                    lower.saturating_add(l),
+                    upper.zip(u).and_then(|(upper, u)| upper.checked_add(u)),
+                )
+            })
+    }
+}
+
+impl<I: fmt::Debug, R> fmt::Debug for InterleaveRandom<I, R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("InterleaveRandom")
+            .field("iterators", &self.iterators)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use super::interleave_random;
+
+    const RUNS: usize = 10000;
+
+    #[test]
+    fn test_interleave_random_keeps_order() {
+        let mut rng = StdRng::seed_from_u64(123);
+        for _ in 0..100 {
+            let merged: Vec<_> =
+                interleave_random(&mut rng, [0..10, 100..105, 200..200, 300..320]).collect();
+            assert_eq!(merged.len(), 35);
+            for start in [0, 100, 300] {
+                let source: Vec<_> = merged
+                    .iter()
+                    .filter(|&&x| x >= start && x < start + 100)
+                    .collect();
+                assert!(source.windows(2).all(|w| w[0] < w[1]));
+            }
+        }
+    }
+
+    #[test]
+    fn test_interleave_random_is_uniform() {
+        // Each of the 10 interleavings of two elements with three elements should be equally likely
+        let mut counts = std::collections::BTreeMap::new();
+        let mut rng = StdRng::seed_from_u64(123);
+
+        for _ in 0..RUNS {
+            let pattern: Vec<bool> = interleave_random(&mut rng, [0..2, 10..13])
+                .map(|x| x < 10)
+                .collect();
+            *counts.entry(pattern).or_insert(0_usize) += 1;
+        }
+
+        insta::assert_debug_snapshot!(counts);
+        assert_eq!(counts.len(), 10);
+        for count in counts.into_values() {
+            assert!(count > RUNS / 10 * 9 / 10);
+            assert!(count < RUNS / 10 * 11 / 10);
+        }
+    }
+
+    #[test]
+    fn test_interleave_random_unhinted() {
+        let mut rng = StdRng::seed_from_u64(123);
+        let sources = [0..10, 10..20, 20..20].map(|source| source.filter(|_| true));
+        let mut merged: Vec<_> = interleave_random(&mut rng, sources).collect();
+        merged.sort_unstable();
+        assert!(merged.into_iter().eq(0..20));
+    }
+
+    #[test]
+    fn test_interleave_random_size_hint() {
+        let mut rng = StdRng::seed_from_u64(123);
+        let mut merged = interleave_random(&mut rng, [0..10, 10..15]);
+        assert_eq!(merged.size_hint(), (15, Some(15)));
+        merged.next();
+        assert_eq!(merged.size_hint(), (14, Some(14)));
+        assert_eq!(merged.count(), 14);
+
+        let empty = interleave_random(&mut rng, Vec::<core::ops::Range<usize>>::new());
+        assert_eq!(empty.size_hint(), (0, Some(0)));
+    }
+}
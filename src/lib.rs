@@ -33,6 +33,20 @@
 //! }
 //! ```
 //!
+//! ## Value stability
+//!
+//! Given a random number generator in the same state, every method returns the same result
+//! on every platform, whatever its pointer width.
+//! All random indices and counts below `u32::MAX` are drawn and compared using 32-bit arithmetic to make this possible.
+//! This holds as long as the iterator has fewer than `u32::MAX` elements.
+//!
+//! The order in which `choose_unique` and `choose_unique_by_key` return their elements depends on the hasher,
+//! so it is not covered, but which element is chosen from each set of duplicates is.
+//!
+//! Results may change between versions. Any change is listed in the changelog.
+//! To reproduce the results of an older release, use the `Kindness` trait from the module for that release,
+//! e.g. [`v0_4::Kindness`] or [`v0_5::Kindness`], instead of [`Kindness`].
+//!
 //! ## Readme Docs
 //!
 //! You can find the crate's readme documentation on the
@@ -41,43 +55,432 @@
 //! [crates.io]: https://crates.io/crates/kindness
 //! [`README.md`]: https://github.com/wainwrightmark/kindness
 
+#[cfg(any(test, feature = "alloc"))]
+extern crate alloc;
+
+/// Weighted sampling with alias tables
+#[cfg(any(test, feature = "alloc"))]
+pub mod alias;
+pub mod array_vec;
+#[cfg(any(test, feature = "alloc"))]
+pub mod bootstrap;
+mod chosen;
 mod coin_flipper;
+#[cfg(any(test, feature = "hashbrown"))]
+mod collection;
+mod counter;
+pub mod dedup;
+/// Weighted sampling with changing weights
+#[cfg(any(test, feature = "alloc"))]
+pub mod dynamic_weighted;
+#[cfg(any(test, feature = "std"))]
+pub mod external;
+mod fallible;
+/// Choosing distinct indices
+#[cfg(any(test, feature = "alloc"))]
+pub mod index;
+#[cfg(any(test, feature = "alloc"))]
+pub mod interleave;
+#[cfg(any(test, feature = "alloc"))]
+mod pareto;
+#[cfg(any(test, feature = "alloc"))]
+mod select;
+#[cfg(any(test, feature = "alloc"))]
+pub mod shuffle;
+#[cfg(any(test, feature = "alloc"))]
+pub mod split;
+#[cfg(test)]
+mod stability;
+pub mod try_rng;
 mod unique;
 ///Uniform distribution
 pub mod uniform;
+/// The algorithms of kindness 0.4
+pub mod v0_4;
+/// The algorithms of kindness 0.5
+pub mod v0_5;
+/// Counting with wider integers
+pub mod wide;
 
 use coin_flipper::CoinFlipper;
+#[cfg(any(test, feature = "hashbrown"))]
+pub use collection::CollectionKindness;
+pub use chosen::Chosen;
+pub use counter::Counter;
+#[cfg(any(test, feature = "std"))]
+pub use external::external_shuffle;
+#[cfg(any(test, feature = "alloc"))]
+pub use interleave::interleave_random;
+#[cfg(any(test, feature = "hashbrown"))]
+pub use unique::UniqueChooser;
 use core::cmp::Ordering;
 use core::hash::{BuildHasher, Hash};
 use rand::Rng;
 
 impl<T: Iterator + Sized> Kindness for T {}
 
+#[cfg(any(test, feature = "alloc"))]
+fn choose_multiple<I: Iterator, R: Rng>(
+    mut iterator: I,
+    rng: &mut R,
+    amount: usize,
+) -> alloc::vec::Vec<I::Item> {
+    let (lower, upper) = iterator.size_hint();
+
+    if upper == Some(lower) {
+        let indices = index::sample_distinct_ascending(rng, lower, amount.min(lower));
+        let mut result = alloc::vec::Vec::with_capacity(indices.len());
+        let mut next = 0;
+        for i in indices {
+            match iterator.nth(i - next) {
+                Some(item) => result.push(item),
+                None => break,
+            }
+            next = i + 1;
+        }
+        return result;
+    }
+
+    let mut reservoir = alloc::vec::Vec::with_capacity(amount);
+    reservoir.extend(iterator.by_ref().take(amount));
+
+    // Continue unless the iterator was exhausted
+    //
+    // note: this prevents iterators that "restart" from causing problems.
+    // If the iterator stops once, then so do we.
+    if reservoir.len() == amount && amount > 0 {
+        let mut coin_flipper = CoinFlipper::new(rng);
+        let mut consumed = amount;
+        for elem in iterator {
+            consumed = consumed.saturating_add(1);
+            let k = coin_flipper.gen_index_below(consumed);
+            if let Some(slot) = reservoir.get_mut(k) {
+                *slot = elem;
+            }
+        }
+    } else {
+        // Don't hang onto extra memory. There is a corner case where
+        // `amount` was much less than `self.len()`.
+        reservoir.shrink_to_fit();
+    }
+    reservoir
+}
+
+#[cfg(any(test, feature = "alloc"))]
+fn choose_multiple_ordered<I: Iterator, R: Rng>(
+    mut iterator: I,
+    rng: &mut R,
+    amount: usize,
+) -> alloc::vec::Vec<I::Item> {
+    let (lower, upper) = iterator.size_hint();
+
+    if upper == Some(lower) {
+        let mut skips = VitterSkips::new(amount.min(lower), lower);
+        let mut result = alloc::vec::Vec::with_capacity(skips.needed);
+        let mut coin_flipper = CoinFlipper::new(rng);
+        while let Some(skip) = skips.next_skip(&mut coin_flipper) {
+            match iterator.nth(skip) {
+                Some(item) => result.push(item),
+                None => break,
+            }
+        }
+        return result;
+    }
+
+    let mut reservoir: alloc::vec::Vec<(usize, I::Item)> =
+        alloc::vec::Vec::with_capacity(amount);
+    reservoir.extend(iterator.by_ref().take(amount).enumerate());
+
+    // Continue unless the iterator was exhausted
+    if reservoir.len() == amount && amount > 0 {
+        let mut coin_flipper = CoinFlipper::new(rng);
+        let mut consumed = amount;
+        for elem in iterator {
+            let position = consumed;
+            consumed = consumed.saturating_add(1);
+            let k = coin_flipper.gen_index_below(consumed);
+            if let Some(slot) = reservoir.get_mut(k) {
+                *slot = (position, elem);
+            }
+        }
+        reservoir.sort_unstable_by_key(|(position, _)| *position);
+    }
+    reservoir.into_iter().map(|(_, item)| item).collect()
+}
+
+/// Method D is faster than Method A while fewer than one in this many of the remaining elements are needed.
+#[cfg(any(test, feature = "alloc"))]
+const VITTER_ALPHA_INVERSE: usize = 13;
+
+/// The numbers of elements to skip before each chosen element when choosing `needed` of `remaining` elements in order.
+///
+/// Uses Vitter's Method D, which needs a constant expected number of random numbers and operations
+/// for each chosen element however many elements are skipped.
+/// As Vitter does, this falls back to Method A once at least one in [`VITTER_ALPHA_INVERSE`] of the remaining elements is needed.
+/// `exp` and `ln` come from `libm`, which is exactly the same on every platform and works in `no_std`.
+#[cfg(any(test, feature = "alloc"))]
+struct VitterSkips {
+    needed: usize,
+    remaining: usize,
+    /// Method D's `V'` for the current value of `needed`, if it was kept from the previous skip
+    v_prime: Option<f64>,
+}
+
+#[cfg(any(test, feature = "alloc"))]
+impl VitterSkips {
+    fn new(needed: usize, remaining: usize) -> Self {
+        debug_assert!(needed <= remaining);
+        Self {
+            needed,
+            remaining,
+            v_prime: None,
+        }
+    }
+
+    /// The number of elements to skip before the next chosen element, or [`None`] if no more are needed.
+    fn next_skip<R: Rng>(&mut self, coin_flipper: &mut CoinFlipper<R>) -> Option<usize> {
+        let skip = match self.needed {
+            0 => return None,
+            1 => coin_flipper.gen_index_below(self.remaining),
+            needed if needed.saturating_mul(VITTER_ALPHA_INVERSE) < self.remaining => {
+                self.method_d(&mut coin_flipper.rng)
+            }
+            needed => method_a(&mut coin_flipper.rng, needed, self.remaining),
+        };
+        self.remaining -= skip + 1;
+        self.needed -= 1;
+        Some(skip)
+    }
+
+    /// Vitter's Method D for `1 < needed` and `needed * VITTER_ALPHA_INVERSE < remaining`.
+    fn method_d<R: Rng>(&mut self, rng: &mut R) -> usize {
+        let n = self.needed as f64;
+        let big_n = self.remaining as f64;
+        let n_inv = 1.0 / n;
+        let n_min_1_inv = 1.0 / (n - 1.0);
+        // The skip is always less than `qu1`
+        let qu1 = self.remaining - self.needed + 1;
+        let qu1_real = qu1 as f64;
+        let mut v_prime = match self.v_prime.take() {
+            Some(v_prime) => v_prime,
+            None => uniform_power(rng, n_inv),
+        };
+        loop {
+            // D2: `x` has a density close to that of the skip
+            let (x, skip) = loop {
+                let x = big_n * (1.0 - v_prime);
+                // Truncation is flooring as `x` is not negative
+                let skip = x as usize;
+                if skip < qu1 {
+                    break (x, skip);
+                }
+                v_prime = uniform_power(rng, n_inv);
+            };
+            let u: f64 = rng.sample(rand::distr::Open01);
+            let skip_real = skip as f64;
+
+            // D3: accept with the cheap squeeze test
+            let y1 = libm::exp(libm::log(u * big_n / qu1_real) * n_min_1_inv);
+            v_prime = y1 * (1.0 - x / big_n) * (qu1_real / (qu1_real - skip_real));
+            if v_prime <= 1.0 {
+                // Given acceptance, `v_prime` is distributed as `V'` for one fewer needed element
+                self.v_prime = Some(v_prime);
+                return skip;
+            }
+
+            // D4: accept with the exact test, which takes `min(skip, needed - 1)` steps
+            let mut y2 = 1.0;
+            let mut top = big_n - 1.0;
+            let (mut bottom, limit) = if self.needed - 1 > skip {
+                (big_n - n, self.remaining - skip)
+            } else {
+                (big_n - skip_real - 1.0, qu1)
+            };
+            for _ in limit..self.remaining {
+                y2 = (y2 * top) / bottom;
+                top -= 1.0;
+                bottom -= 1.0;
+            }
+            if big_n / (big_n - x) >= y1 * libm::exp(libm::log(y2) * n_min_1_inv) {
+                return skip;
+            }
+            v_prime = uniform_power(rng, n_inv);
+        }
+    }
+}
+
+/// Returns `U^exponent` for `U` uniform in `(0, 1)`
+#[cfg(any(test, feature = "alloc"))]
+fn uniform_power<R: Rng>(rng: &mut R, exponent: f64) -> f64 {
+    let u: f64 = rng.sample(rand::distr::Open01);
+    libm::exp(libm::log(u) * exponent)
+}
+
+/// The number of elements to skip before the next chosen element when choosing `needed` of `remaining` elements
+/// in order, using Vitter's Method A.
+///
+/// This needs one random number but takes time proportional to the skip,
+/// so [`VitterSkips`] only uses it when the skips are short.
+#[cfg(any(test, feature = "alloc"))]
+fn method_a<R: Rng>(rng: &mut R, needed: usize, remaining: usize) -> usize {
+    debug_assert!(0 < needed && needed <= remaining);
+    let v: f64 = rng.random();
+    let mut skip = 0;
+    let mut top = (remaining - needed) as f64;
+    let mut denominator = remaining as f64;
+    // The probability of skipping more than `skip` elements
+    let mut quotient = top / denominator;
+    while quotient > v {
+        skip += 1;
+        top -= 1.0;
+        denominator -= 1.0;
+        quotient *= top / denominator;
+    }
+    skip
+}
+
+fn choose_multiple_array<I: Iterator, R: Rng, const K: usize>(
+    mut iterator: I,
+    rng: &mut R,
+) -> array_vec::ArrayVec<I::Item, K> {
+    let mut reservoir = array_vec::ArrayVec::new();
+    for item in iterator.by_ref().take(K) {
+        // `take` means that this always fits
+        let _ = reservoir.try_push(item);
+    }
+
+    // Continue unless the iterator was exhausted
+    if reservoir.is_full() && K > 0 {
+        let mut coin_flipper = CoinFlipper::new(rng);
+        let mut consumed = K;
+        for elem in iterator {
+            consumed = consumed.saturating_add(1);
+            let k = coin_flipper.gen_index_below(consumed);
+            if let Some(slot) = reservoir.get_mut(k) {
+                *slot = elem;
+            }
+        }
+    }
+    reservoir
+}
+
+fn choose_item<I: Iterator + Sized, R: Rng, C: Counter>(
+    mut iterator: I,
+    rng: &mut R,
+) -> Option<I::Item> {
+    let (mut lower, mut upper) = iterator.size_hint();
+    let mut result = None;
+
+    // Handling for this condition outside the loop allows the optimizer to eliminate the loop
+    // when the Iterator is an ExactSizeIterator. This has a large performance impact on e.g.
+    // seq_iter_choose_from_1000.
+    if upper == Some(lower) {
+        return if lower == 0 {
+            None
+        } else {
+            iterator.nth(CoinFlipper::new(rng).gen_index_below(lower))
+        };
+    }
+
+    //let mut choice_iterator = Chooser::new_zero(rng);
+    let mut coin_flipper = CoinFlipper::new(rng);
+    let mut consumed = C::ZERO;
+
+    // Continue until the iterator is exhausted
+    loop {
+        if lower > 1 {
+            let ix = coin_flipper.gen_index_below(C::from_usize(lower).saturating_add(consumed));
+            let skip = match ix.try_into_usize() {
+                Some(ix) if ix < lower => {
+                    result = iterator.nth(ix);
+                    lower - (ix + 1)
+                }
+                _ => lower,
+            };
+            if upper == Some(lower) {
+                return result;
+            }
+            consumed = consumed.saturating_add(C::from_usize(lower));
+            if skip > 0 {
+                iterator.nth(skip - 1);
+            }
+        } else {
+            consumed = consumed.saturating_add(C::ONE);
+            let skip =
+                coin_flipper.try_skip(consumed.try_into_u32().unwrap_or(u32::MAX)) as usize;
+            let elem = iterator.nth(skip);
+            if elem.is_none() {
+                return result;
+            }
+            consumed = consumed.saturating_add(C::from_usize(skip));
+
+            if coin_flipper.gen_ratio_one_over(consumed) {
+                result = elem;
+            }
+        }
+
+        let hint = iterator.size_hint();
+        lower = hint.0;
+        upper = hint.1;
+    }
+}
+
 fn choose_best_by_key<
     I: Iterator + Sized,
     B: Ord,
     R: Rng,
     F: FnMut(&I::Item) -> B,
+    C: Counter,
     const MAX: bool,
 >(
-    mut iterator: I,
+    iterator: I,
     rng: &mut R,
-    mut f: F,
+    f: F,
 ) -> Option<I::Item> {
-    let Some(first)  = iterator.next() else {
+    choose_best_by_key_with_stats::<I, B, R, F, C, MAX>(iterator, rng, f).map(|chosen| chosen.item)
+}
+
+fn choose_best_by_key_with_stats<
+    I: Iterator + Sized,
+    B: Ord,
+    R: Rng,
+    F: FnMut(&I::Item) -> B,
+    C: Counter,
+    const MAX: bool,
+>(
+    iterator: I,
+    rng: &mut R,
+    mut f: F,
+) -> Option<Chosen<I::Item, B, C>> {
+    choose_best_keyed_with_stats::<_, _, _, R, C, MAX>(iterator.map(|item| (f(&item), item)), rng)
+}
+
+/// Chooses the best element from `(key, element)` pairs
+fn choose_best_keyed_with_stats<
+    I: Iterator<Item = (B, T)>,
+    T,
+    B: Ord,
+    R: Rng,
+    C: Counter,
+    const MAX: bool,
+>(
+    mut iterator: I,
+    rng: &mut R,
+) -> Option<Chosen<T, B, C>> {
+    let Some((mut current_key, first)) = iterator.next() else {
         return None;
     };
 
-    let mut current_key = f(&first);
     let mut current = first;
     let mut coin_flipper = coin_flipper::CoinFlipper::new(rng);
-    let mut consumed = 1;
+    let mut consumed = C::ONE;
+    let mut total_seen = C::ONE;
 
-    for item in iterator {
-        let item_key = f(&item);
+    for (item_key, item) in iterator {
+        total_seen = total_seen.saturating_add(C::ONE);
         match item_key.cmp(&current_key) {
             core::cmp::Ordering::Equal => {
-                consumed += 1;
+                consumed = consumed.saturating_add(C::ONE);
                 //Choose either iter or current randomly, see random_element for more
                 if coin_flipper.gen_ratio_one_over(consumed) {
                     current = item;
@@ -87,25 +490,48 @@ fn choose_best_by_key<
                 if MAX == (ordering == core::cmp::Ordering::Greater) {
                     current_key = item_key; //this is the new maximum
                     current = item;
-                    consumed = 1;
+                    consumed = C::ONE;
                 }
             }
         }
     }
 
-    Some(current)
+    Some(Chosen {
+        item: current,
+        key: current_key,
+        ties: consumed,
+        total_seen,
+    })
 }
 
 fn choose_best_by<
     I: Iterator + Sized,
     R: Rng,
     F: FnMut(&I::Item, &I::Item) -> Ordering,
+    C: Counter,
+    const MAX: bool,
+>(
+    iterator: I,
+    rng: &mut R,
+    compare: F,
+) -> Option<I::Item>
+where
+    I::Item: Ord,
+{
+    choose_best_by_with_stats::<I, R, F, C, MAX>(iterator, rng, compare).map(|chosen| chosen.item)
+}
+
+fn choose_best_by_with_stats<
+    I: Iterator + Sized,
+    R: Rng,
+    F: FnMut(&I::Item, &I::Item) -> Ordering,
+    C: Counter,
     const MAX: bool,
 >(
     mut iterator: I,
     rng: &mut R,
     mut compare: F,
-) -> Option<I::Item>
+) -> Option<Chosen<I::Item, (), C>>
 where
     I::Item: Ord,
 {
@@ -115,12 +541,14 @@ where
 
     let mut current = first;
     let mut coin_flipper = coin_flipper::CoinFlipper::new(rng);
-    let mut consumed = 1;
+    let mut consumed = C::ONE;
+    let mut total_seen = C::ONE;
 
     for item in iterator {
+        total_seen = total_seen.saturating_add(C::ONE);
         match compare(&item, &current) {
             core::cmp::Ordering::Equal => {
-                consumed += 1;
+                consumed = consumed.saturating_add(C::ONE);
                 if coin_flipper.gen_ratio_one_over(consumed) {
                     current = item;
                 }
@@ -128,13 +556,194 @@ where
             ordering => {
                 if MAX == (ordering == core::cmp::Ordering::Greater) {
                     current = item; //this is the new maximum
-                    consumed = 1;
+                    consumed = C::ONE;
+                }
+            }
+        }
+    }
+
+    Some(Chosen {
+        item: current,
+        key: (),
+        ties: consumed,
+        total_seen,
+    })
+}
+
+#[cfg(any(test, feature = "hashbrown"))]
+fn choose_unique_with_hasher_in<
+    I: Iterator + Sized,
+    R: Rng,
+    S: BuildHasher,
+    A: allocator_api2::alloc::Allocator + Clone,
+    C: Counter,
+>(
+    iterator: I,
+    rng: &mut R,
+    hash_builder: S,
+    alloc: A,
+) -> unique::iterators::Unique<I::Item, A, C>
+where
+    I::Item: Hash + Eq,
+{
+    let mut chooser = unique::UniqueChooser::<I::Item, S, A, C>::new_in(hash_builder, alloc);
+    chooser.extend(iterator, rng);
+    chooser.into_iter()
+}
+
+fn choose_unique_array<I: Iterator + Sized, R: Rng, C: Counter, const N: usize>(
+    iterator: I,
+    rng: &mut R,
+) -> Result<array_vec::ArrayVec<I::Item, N>, array_vec::CapacityError>
+where
+    I::Item: Eq,
+{
+    let mut chosen: array_vec::ArrayVec<I::Item, N> = array_vec::ArrayVec::new();
+    let mut counts = [C::ZERO; N];
+    let mut coin_flipper = CoinFlipper::new(rng);
+    for item in iterator {
+        let position = chosen.iter().position(|other| item.eq(other));
+        match position {
+            Some(index) => {
+                let new_count = counts[index].saturating_add(C::ONE);
+                counts[index] = new_count;
+
+                if coin_flipper.gen_ratio_one_over(new_count) {
+                    //We have randomly decided to change the key to the new item
+                    *chosen.get_mut(index).expect("Index was found") = item;
+                }
+            }
+            None => {
+                let index = chosen.len();
+                chosen.try_push(item).map_err(|_| array_vec::CapacityError)?;
+                counts[index] = C::ONE;
+            }
+        }
+    }
+    Ok(chosen)
+}
+
+#[cfg(any(test, feature = "hashbrown"))]
+fn choose_unique_by_key_with_hasher_in<
+    I: Iterator + Sized,
+    R: Rng,
+    K: Eq + Hash,
+    F: FnMut(&I::Item) -> K,
+    S: BuildHasher,
+    A: allocator_api2::alloc::Allocator + Clone,
+    C: Counter,
+>(
+    iterator: I,
+    rng: &mut R,
+    mut get_key: F,
+    hash_builder: S,
+    alloc: A,
+) -> unique::iterators::UniqueByKey<K, I::Item, A, C> {
+    choose_unique_keyed_with_hasher_in::<_, _, R, K, S, A, C>(
+        iterator.map(|element| (get_key(&element), element)),
+        rng,
+        hash_builder,
+        alloc,
+    )
+}
+
+/// Chooses unique elements from `(key, element)` pairs
+#[cfg(any(test, feature = "hashbrown"))]
+fn choose_unique_keyed_with_hasher_in<
+    I: Iterator<Item = (K, T)>,
+    T,
+    R: Rng,
+    K: Eq + Hash,
+    S: BuildHasher,
+    A: allocator_api2::alloc::Allocator + Clone,
+    C: Counter,
+>(
+    iterator: I,
+    rng: &mut R,
+    hash_builder: S,
+    alloc: A,
+) -> unique::iterators::UniqueByKey<K, T, A, C> {
+    use hashbrown::{hash_map::Entry, HashMap};
+    let mut map: HashMap<K, (T, C), S, A> = HashMap::with_hasher_in(hash_builder, alloc);
+    let mut coin_flipper = CoinFlipper::new(rng);
+    for (v, element) in iterator {
+        let entry = map
+            .entry(v)
+            .and_modify(|(e, c)| *c = c.saturating_add(C::ONE));
+
+        match entry {
+            Entry::Occupied(mut occupied) => {
+                let (previous, new_count) = occupied.get_mut();
+                if coin_flipper.gen_ratio_one_over(*new_count) {
+                    *previous = element;
                 }
             }
+            Entry::Vacant(vacant) => {
+                vacant.insert((element, C::ONE));
+            }
         }
     }
 
-    Some(current)
+    unique::iterators::UniqueByKey::new(map.into_values())
+}
+
+#[cfg(any(test, feature = "hashbrown"))]
+fn choose_mode_with_hasher_in<
+    I: Iterator + Sized,
+    R: Rng,
+    S: BuildHasher,
+    A: allocator_api2::alloc::Allocator + Clone,
+    C: Counter,
+>(
+    iterator: I,
+    rng: &mut R,
+    hash_builder: S,
+    alloc: A,
+) -> Option<I::Item>
+where
+    I::Item: Hash + Eq,
+{
+    let counted =
+        choose_unique_with_hasher_in::<I, R, S, A, C>(iterator, rng, hash_builder, alloc).into_counted();
+    choose_best_by_key::<_, C, R, _, usize, true>(counted, rng, |(_, count)| *count).map(|(item, _)| item)
+}
+
+#[cfg(any(test, feature = "hashbrown"))]
+fn choose_mode_by_key_with_hasher_in<
+    I: Iterator + Sized,
+    R: Rng,
+    K: Eq + Hash,
+    F: FnMut(&I::Item) -> K,
+    S: BuildHasher,
+    A: allocator_api2::alloc::Allocator + Clone,
+    C: Counter,
+>(
+    iterator: I,
+    rng: &mut R,
+    get_key: F,
+    hash_builder: S,
+    alloc: A,
+) -> Option<I::Item> {
+    let counted = choose_unique_by_key_with_hasher_in::<I, R, K, F, S, A, C>(
+        iterator,
+        rng,
+        get_key,
+        hash_builder,
+        alloc,
+    )
+    .into_counted();
+    choose_best_by_key::<_, C, R, _, usize, true>(counted, rng, |(_, count)| *count).map(|(item, _)| item)
+}
+
+/// Pairs each [`Ok`] element with its key, stopping at the first error
+fn keyed<T, E, B, I: Iterator<Item = Result<T, E>>, F: FnMut(&T) -> Result<B, E>>(
+    iterator: I,
+    mut f: F,
+) -> impl Iterator<Item = Result<(B, T), E>> {
+    iterator.map(move |item| {
+        let item = item?;
+        Ok((f(&item)?, item))
+    })
 }
 
 /// An [`Iterator`] blanket implementation that provides extra adaptors and
@@ -146,107 +755,89 @@ where
     /// Return a random element of the iterator.  
     /// Returns none if the iterator is empty.  
     /// If the iterator has more than `usize::Max` elements, later elements will be slightly more likely.
+    /// Use [`Kindness::wide_counter`] to avoid this.
     /// Will iterate the entire enumerable unless it has a size hint which indicates an exact length.
     #[inline]
     fn choose_item<R: Rng>(mut self, rng: &mut R) -> Option<Self::Item> {
-        let (mut lower, mut upper) = self.size_hint();
-        let mut result = None;
-
-        // Handling for this condition outside the loop allows the optimizer to eliminate the loop
-        // when the Iterator is an ExactSizeIterator. This has a large performance impact on e.g.
-        // seq_iter_choose_from_1000.
-        if upper == Some(lower) {
-            return if lower == 0 {
-                None
-            } else {
-                self.nth(gen_index(rng, lower))
-            };
-        }
-
-        //let mut choice_iterator = Chooser::new_zero(rng);
-        let mut coin_flipper = CoinFlipper::new(rng);
-        let mut consumed = 0;
+        choose_item::<Self, R, usize>(self, rng)
+    }
 
-        // Continue until the iterator is exhausted
-        loop {
-            if lower > 1 {
-                let ix = gen_index(coin_flipper.rng, lower + consumed);
-                let skip = if ix < lower {
-                    result = self.nth(ix);
-                    lower - (ix + 1)
-                } else {
-                    lower
-                };
-                if upper == Some(lower) {
-                    return result;
-                }
-                consumed += lower;
-                if skip > 0 {
-                    self.nth(skip - 1);
-                }
-            } else {
-                consumed += 1;
-                let skip = coin_flipper.try_skip(consumed as u32) as usize;
-                let elem = self.nth(skip);
-                if elem.is_none() {
-                    return result;
-                }
-                consumed += skip;
+    /// Collects `amount` values at random from the iterator into a vector.
+    ///
+    /// Although the elements are selected randomly, the order of elements in
+    /// the buffer is neither stable nor fully random. If random ordering is
+    /// desired, shuffle the result.
+    ///
+    /// The length of the returned vector equals `amount` unless the iterator
+    /// contains insufficient elements, in which case it equals the number of
+    /// elements available.
+    ///
+    /// If the iterator has a size hint which indicates an exact length, only the chosen elements are visited
+    /// (with [`Iterator::nth`]) and they are returned in the order of the iterator.
+    /// Otherwise the entire iterator is consumed.
+    /// If the iterator has more than `usize::Max` elements, later elements will be slightly more likely.
+    #[cfg(any(test, feature = "alloc"))]
+    fn choose_multiple<R: Rng>(self, rng: &mut R, amount: usize) -> alloc::vec::Vec<Self::Item> {
+        choose_multiple(self, rng, amount)
+    }
 
-                if coin_flipper.gen_ratio_one_over(consumed) {
-                    result = elem;
-                }
-            }
+    /// Collects `amount` values at random from the iterator into a vector, in the order of the iterator.
+    ///
+    /// If the iterator has a size hint which indicates an exact length, elements are chosen one at a time
+    /// by skipping a random number of elements with [`Iterator::nth`], using Vitter's Method D,
+    /// so only the chosen elements are visited and each needs a constant expected number of random numbers.
+    /// Otherwise the entire iterator is consumed into a reservoir which remembers the position of each element.
+    ///
+    /// The length of the returned vector equals `amount` unless the iterator
+    /// contains insufficient elements, in which case it equals the number of
+    /// elements available.
+    /// If the iterator has more than `usize::Max` elements, later elements will be slightly more likely.
+    ///
+    /// ```
+    /// use kindness::*;
+    /// use rand::SeedableRng;
+    ///
+    /// let mut rng = rand::rngs::StdRng::seed_from_u64(123);
+    /// let sample = (0..100).filter(|x| x % 2 == 0).choose_multiple_ordered(&mut rng, 5);
+    /// assert!(sample.windows(2).all(|w| w[0] < w[1]));
+    /// ```
+    #[cfg(any(test, feature = "alloc"))]
+    fn choose_multiple_ordered<R: Rng>(
+        self,
+        rng: &mut R,
+        amount: usize,
+    ) -> alloc::vec::Vec<Self::Item> {
+        choose_multiple_ordered(self, rng, amount)
+    }
 
-            let hint = self.size_hint();
-            lower = hint.0;
-            upper = hint.1;
-        }
-    }
-
-    // /// Collects `amount` values at random from the iterator into a vector.
-    // ///
-    // /// This is equivalent to `choose_multiple_fill` except for the result type.
-    // ///
-    // /// Although the elements are selected randomly, the order of elements in
-    // /// the buffer is neither stable nor fully random. If random ordering is
-    // /// desired, shuffle the result.
-    // ///
-    // /// The length of the returned vector equals `amount` unless the iterator
-    // /// contains insufficient elements, in which case it equals the number of
-    // /// elements available.
-    // ///
-    // /// Complexity is `O(n)` where `n` is the length of the iterator.
-    // /// For slices, prefer [`SliceRandom::choose_multiple`].
-    // #[cfg(feature = "std")]
-    // fn choose_multiple<R>(mut self, rng: &mut R, amount: usize) -> Vec<Self::Item>
-    // where R: Rng + ?Sized {
-    //     todo!()
-    //     // let mut reservoir = Vec::with_capacity(amount);
-    //     // reservoir.extend(self.by_ref().take(amount));
-
-    //     // // Continue unless the iterator was exhausted
-    //     // //
-    //     // // note: this prevents iterators that "restart" from causing problems.
-    //     // // If the iterator stops once, then so do we.
-    //     // if reservoir.len() == amount {
-
-    //     //     let coin_flipper = CoinFlipper::new(rng);
-    //     //     //coin_flipper.rng
-
-    //     //     for (i, elem) in self.enumerate() {
-    //     //         let k = gen_index(rng, i + 1 + amount);
-    //     //         if let Some(slot) = reservoir.get_mut(k) {
-    //     //             *slot = elem;
-    //     //         }
-    //     //     }
-    //     // } else {
-    //     //     // Don't hang onto extra memory. There is a corner case where
-    //     //     // `amount` was much less than `self.len()`.
-    //     //     reservoir.shrink_to_fit();
-    //     // }
-    //     // reservoir
-    // }
+    /// Collects `K` values at random from the iterator into an inline [`array_vec::ArrayVec`], without allocating.
+    ///
+    /// Uses the same reservoir sampling as [`Kindness::choose_multiple`],
+    /// so for iterators without an exact size hint it chooses the same elements in the same order.
+    /// The order of elements is neither stable nor fully random.
+    ///
+    /// The length of the returned vector equals `K` unless the iterator
+    /// contains insufficient elements, in which case it equals the number of
+    /// elements available.
+    /// The entire iterator is consumed.
+    /// If the iterator has more than `usize::Max` elements, later elements will be slightly more likely.
+    ///
+    /// ```
+    /// use kindness::*;
+    /// use rand::SeedableRng;
+    ///
+    /// let mut rng = rand::rngs::StdRng::seed_from_u64(123);
+    /// let neighbours = ["north", "east", "south", "west"]
+    ///     .iter()
+    ///     .choose_multiple_array::<3, _>(&mut rng);
+    /// assert_eq!(neighbours.len(), 3);
+    /// ```
+    fn choose_multiple_array<const K: usize, R: Rng>(
+        self,
+        rng: &mut R,
+    ) -> array_vec::ArrayVec<Self::Item, K> {
+        choose_multiple_array(self, rng)
+    }
 
     /// Returns a random maximum element with respect to the specified comparison function.
     ///
@@ -268,7 +859,7 @@ where
         rng: &mut R,
         mut f: F,
     ) -> Option<Self::Item> {
-        choose_best_by_key::<Self, B, R, F, true>(self, rng, f)
+        choose_best_by_key::<Self, B, R, F, usize, true>(self, rng, f)
     }
 
     /// Returns a random maximum element.
@@ -283,7 +874,7 @@ where
     where
         Self::Item: Ord,
     {
-        choose_best_by::<Self, R, F, true>(self, rng, compare)
+        choose_best_by::<Self, R, F, usize, true>(self, rng, compare)
     }
 
     /// Return a random minimum element of the iterator.  
@@ -305,7 +896,7 @@ where
         rng: &mut R,
         mut f: F,
     ) -> Option<Self::Item> {
-        choose_best_by_key::<Self, B, R, F, false>(self, rng, f)
+        choose_best_by_key::<Self, B, R, F, usize, false>(self, rng, f)
     }
 
     /// Returns a random minimum element.
@@ -320,77 +911,643 @@ where
     where
         Self::Item: Ord,
     {
-        choose_best_by::<Self, R, F, false>(self, rng, compare)
+        choose_best_by::<Self, R, F, usize, false>(self, rng, compare)
     }
 
-    /// Returns an iterator over unique elements of this iterator.
-    /// Elements are chosen randomly from the duplicates.
-    /// Duplicates are detected using hash and equality.
-    /// Uses the global allocator and the default hasher which is safe from HashDos attacks.
-    #[cfg(any(test, all(feature = "hashbrown", feature = "std")))]
-    fn choose_unique<R: Rng>(
-        mut self,
-        rng: &mut R,
-    ) -> unique::iterators::Unique<Self::Item, allocator_api2::alloc::Global>
+    /// Like [`Kindness::choose_max`] but also returns the number of elements tied for maximum
+    /// and the total number of elements.
+    ///
+    /// ```
+    /// use kindness::*;
+    /// use rand::SeedableRng;
+    ///
+    /// let mut rng = rand::rngs::StdRng::seed_from_u64(123);
+    /// let chosen = [3, 2, 1, 2, 3].iter().choose_max_with_stats(&mut rng).unwrap();
+    /// assert_eq!(*chosen.item, 3);
+    /// assert_eq!(chosen.ties, 2);
+    /// assert_eq!(chosen.total_seen, 5);
+    /// ```
+    fn choose_max_with_stats<R: Rng>(self, rng: &mut R) -> Option<Chosen<Self::Item>>
     where
-        Self::Item: Hash + Eq,
+        Self::Item: Ord,
     {
-        let hash_builder = std::collections::hash_map::RandomState::new();
-        let alloc = allocator_api2::alloc::Global;
-        self.choose_unique_with_hasher_in(rng, hash_builder, alloc)
+        self.choose_max_by_with_stats(rng, Ord::cmp)
     }
 
-    /// Returns an iterator over unique elements of this iterator.
-    /// Elements are chosen randomly from the duplicates.
-    /// Duplicates are detected using hash and equality.
-    ///
-    /// You must supply a `BuildHasher` and an `Allocator` to use this.
-    /// The `std` feature provides a more ergonomic `choose_unique`
-    #[cfg(any(test, all(feature = "hashbrown")))]
-    fn choose_unique_with_hasher_in<
-        R: Rng,
-        S: BuildHasher,
-        A: allocator_api2::alloc::Allocator + Clone,
-    >(
-        mut self,
+    /// Like [`Kindness::choose_max_by_key`] but also returns the maximum key, the number of elements tied for maximum
+    /// and the total number of elements.
+    fn choose_max_by_key_with_stats<B: Ord, R: Rng, F: FnMut(&Self::Item) -> B>(
+        self,
         rng: &mut R,
-        hash_builder: S,
-        alloc: A,
-    ) -> unique::iterators::Unique<Self::Item, A>
+        f: F,
+    ) -> Option<Chosen<Self::Item, B>> {
+        choose_best_by_key_with_stats::<Self, B, R, F, usize, true>(self, rng, f)
+    }
+
+    /// Like [`Kindness::choose_max_by`] but also returns the number of elements tied for maximum
+    /// and the total number of elements.
+    fn choose_max_by_with_stats<R: Rng, F: FnMut(&Self::Item, &Self::Item) -> Ordering>(
+        self,
+        rng: &mut R,
+        compare: F,
+    ) -> Option<Chosen<Self::Item>>
     where
-        Self::Item: Hash + Eq,
+        Self::Item: Ord,
     {
-        use hashbrown::{hash_map::Entry, HashTable};
-        let mut table: HashTable<(Self::Item, usize), A> =
-        HashTable::new_in(alloc);
-        let mut coin_flipper = CoinFlipper::new(rng);
-        for item in self {
-
-            let hash = hash_builder.hash_one(&item);
-
-            let entry = table.entry(hash, |(other, _)| item.eq(other), |(i,_)| hash_builder.hash_one(i));
+        choose_best_by_with_stats::<Self, R, F, usize, true>(self, rng, compare)
+    }
 
-            match entry{
-                hashbrown::hash_table::Entry::Occupied(mut occupied_entry) => {
-                    let new_count = occupied_entry.get().1+ 1;
-                    occupied_entry.get_mut().1 = new_count;
+    /// Like [`Kindness::choose_min`] but also returns the number of elements tied for minimum
+    /// and the total number of elements.
+    fn choose_min_with_stats<R: Rng>(self, rng: &mut R) -> Option<Chosen<Self::Item>>
+    where
+        Self::Item: Ord,
+    {
+        self.choose_min_by_with_stats(rng, Ord::cmp)
+    }
 
-                    if coin_flipper.gen_ratio_one_over(new_count) {
-                        //We have randomly decided to change the key to the new item
-                        occupied_entry.get_mut().0 = item;
-                    }
-                },
-                hashbrown::hash_table::Entry::Vacant(vacant_entry) => {
-                    vacant_entry.insert((item, 1));
-                },
-            }
-        }
+    /// Like [`Kindness::choose_min_by_key`] but also returns the minimum key, the number of elements tied for minimum
+    /// and the total number of elements.
+    fn choose_min_by_key_with_stats<B: Ord, R: Rng, F: FnMut(&Self::Item) -> B>(
+        self,
+        rng: &mut R,
+        f: F,
+    ) -> Option<Chosen<Self::Item, B>> {
+        choose_best_by_key_with_stats::<Self, B, R, F, usize, false>(self, rng, f)
+    }
+
+    /// Like [`Kindness::choose_min_by`] but also returns the number of elements tied for minimum
+    /// and the total number of elements.
+    fn choose_min_by_with_stats<R: Rng, F: FnMut(&Self::Item, &Self::Item) -> Ordering>(
+        self,
+        rng: &mut R,
+        compare: F,
+    ) -> Option<Chosen<Self::Item>>
+    where
+        Self::Item: Ord,
+    {
+        choose_best_by_with_stats::<Self, R, F, usize, false>(self, rng, compare)
+    }
+
+    /// Like [`Kindness::choose_item`] but for an iterator of [`Result`]s.
+    /// Returns the first error if there is one, otherwise a random [`Ok`] value.
+    ///
+    /// Consumes the same random numbers as [`Kindness::choose_item`] would for the [`Ok`] values alone.
+    /// Stops at the first error, but otherwise iterates the entire enumerable
+    /// because every element must be checked for errors.
+    ///
+    /// ```
+    /// use kindness::*;
+    /// use rand::SeedableRng;
+    ///
+    /// let mut rng = rand::rngs::StdRng::seed_from_u64(123);
+    /// let parsed = ["1", "2", "x"].into_iter().map(str::parse::<u32>).try_choose_item(&mut rng);
+    /// assert!(parsed.is_err());
+    /// ```
+    fn try_choose_item<T, E, R: Rng>(self, rng: &mut R) -> Result<Option<T>, E>
+    where
+        Self: Iterator<Item = Result<T, E>>,
+    {
+        fallible::try_with(self, |shunt| choose_item::<_, R, usize>(shunt, rng))
+    }
+
+    /// Like [`Kindness::choose_max`] but for an iterator of [`Result`]s.
+    /// Returns the first error if there is one, otherwise a random maximum [`Ok`] value.
+    fn try_choose_max<T: Ord, E, R: Rng>(self, rng: &mut R) -> Result<Option<T>, E>
+    where
+        Self: Iterator<Item = Result<T, E>>,
+    {
+        self.try_choose_max_by(rng, Ord::cmp)
+    }
+
+    /// Like [`Kindness::choose_max_by_key`] but for an iterator of [`Result`]s and a fallible key function.
+    /// Returns the first error, from either the iterator or `f`, if there is one.
+    /// `f` is not called again after an error.
+    fn try_choose_max_by_key<T, E, B: Ord, R: Rng, F: FnMut(&T) -> Result<B, E>>(
+        self,
+        rng: &mut R,
+        f: F,
+    ) -> Result<Option<T>, E>
+    where
+        Self: Iterator<Item = Result<T, E>>,
+    {
+        fallible::try_with(keyed(self, f), |shunt| {
+            choose_best_keyed_with_stats::<_, _, _, R, usize, true>(shunt, rng).map(|c| c.item)
+        })
+    }
+
+    /// Like [`Kindness::choose_max_by`] but for an iterator of [`Result`]s.
+    /// Returns the first error if there is one, otherwise a random maximum [`Ok`] value.
+    fn try_choose_max_by<T: Ord, E, R: Rng, F: FnMut(&T, &T) -> Ordering>(
+        self,
+        rng: &mut R,
+        compare: F,
+    ) -> Result<Option<T>, E>
+    where
+        Self: Iterator<Item = Result<T, E>>,
+    {
+        fallible::try_with(self, |shunt| {
+            choose_best_by::<_, R, F, usize, true>(shunt, rng, compare)
+        })
+    }
+
+    /// Like [`Kindness::choose_min`] but for an iterator of [`Result`]s.
+    /// Returns the first error if there is one, otherwise a random minimum [`Ok`] value.
+    fn try_choose_min<T: Ord, E, R: Rng>(self, rng: &mut R) -> Result<Option<T>, E>
+    where
+        Self: Iterator<Item = Result<T, E>>,
+    {
+        self.try_choose_min_by(rng, Ord::cmp)
+    }
+
+    /// Like [`Kindness::choose_min_by_key`] but for an iterator of [`Result`]s and a fallible key function.
+    /// Returns the first error, from either the iterator or `f`, if there is one.
+    /// `f` is not called again after an error.
+    fn try_choose_min_by_key<T, E, B: Ord, R: Rng, F: FnMut(&T) -> Result<B, E>>(
+        self,
+        rng: &mut R,
+        f: F,
+    ) -> Result<Option<T>, E>
+    where
+        Self: Iterator<Item = Result<T, E>>,
+    {
+        fallible::try_with(keyed(self, f), |shunt| {
+            choose_best_keyed_with_stats::<_, _, _, R, usize, false>(shunt, rng).map(|c| c.item)
+        })
+    }
+
+    /// Like [`Kindness::choose_min_by`] but for an iterator of [`Result`]s.
+    /// Returns the first error if there is one, otherwise a random minimum [`Ok`] value.
+    fn try_choose_min_by<T: Ord, E, R: Rng, F: FnMut(&T, &T) -> Ordering>(
+        self,
+        rng: &mut R,
+        compare: F,
+    ) -> Result<Option<T>, E>
+    where
+        Self: Iterator<Item = Result<T, E>>,
+    {
+        fallible::try_with(self, |shunt| {
+            choose_best_by::<_, R, F, usize, false>(shunt, rng, compare)
+        })
+    }
+
+    /// Returns a random element equal to the `nth` smallest element (counting from zero).
+    /// Every element equal to the `nth` smallest element is equally likely to be chosen,
+    /// so `choose_nth_smallest(rng, 0)` behaves like [`Kindness::choose_min`].
+    ///
+    /// If the iterator has `nth` or fewer elements, [`None`] is returned.
+    /// Collects the elements and uses randomized quickselect, which takes expected linear time.
+    #[cfg(any(test, feature = "alloc"))]
+    fn choose_nth_smallest<R: Rng>(self, rng: &mut R, nth: usize) -> Option<Self::Item>
+    where
+        Self::Item: Ord,
+    {
+        select::choose_nth(self.collect(), rng, nth, Ord::cmp)
+    }
+
+    /// Returns a random element whose key is equal to the `nth` smallest key (counting from zero)
+    /// from the specified function.
+    /// Every element whose key is equal to the `nth` smallest key is equally likely to be chosen,
+    /// so `choose_nth_smallest_by_key(rng, 0, f)` behaves like [`Kindness::choose_min_by_key`].
+    /// `f` is called exactly once for each element.
+    ///
+    /// If the iterator has `nth` or fewer elements, [`None`] is returned.
+    /// Collects the elements and uses randomized quickselect, which takes expected linear time.
+    #[cfg(any(test, feature = "alloc"))]
+    fn choose_nth_smallest_by_key<B: Ord, R: Rng, F: FnMut(&Self::Item) -> B>(
+        self,
+        rng: &mut R,
+        nth: usize,
+        mut f: F,
+    ) -> Option<Self::Item> {
+        let keyed = self.map(|item| (f(&item), item)).collect();
+        select::choose_nth(keyed, rng, nth, |a, b| a.0.cmp(&b.0)).map(|(_, item)| item)
+    }
+
+    /// Returns a random element whose key is equal to the `q` quantile of the keys from the specified function.
+    /// The quantile is the key at index `floor(q * (len - 1))` in sorted order,
+    /// so `0.0` gives the minimum, `1.0` gives the maximum and `0.5` gives the (lower) median.
+    /// Every element with that key is equally likely to be chosen.
+    /// `f` is called exactly once for each element.
+    ///
+    /// If the iterator is empty, [`None`] is returned.
+    /// Panics if `q` is not between zero and one.
+    ///
+    /// ```
+    /// use kindness::*;
+    /// use rand::SeedableRng;
+    ///
+    /// let mut rng = rand::rngs::StdRng::seed_from_u64(123);
+    /// let median = [5, 1, 4, 2, 3].into_iter().choose_quantile_by_key(&mut rng, 0.5, |x| *x);
+    /// assert_eq!(median, Some(3));
+    /// ```
+    #[cfg(any(test, feature = "alloc"))]
+    fn choose_quantile_by_key<B: Ord, R: Rng, F: FnMut(&Self::Item) -> B>(
+        self,
+        rng: &mut R,
+        q: f64,
+        mut f: F,
+    ) -> Option<Self::Item> {
+        let keyed: alloc::vec::Vec<_> = self.map(|item| (f(&item), item)).collect();
+        let nth = select::quantile_index(keyed.len(), q);
+        select::choose_nth(keyed, rng, nth, |a, b| a.0.cmp(&b.0)).map(|(_, item)| item)
+    }
+
+    /// Returns a random element which is not dominated by any other element.
+    /// `dominance(a, b)` should return `Some(Greater)` if `a` dominates `b`, `Some(Less)` if `b` dominates `a`
+    /// and `Some(Equal)` or `None` if neither dominates the other.
+    /// Every element of the Pareto front is equally likely to be chosen.
+    ///
+    /// If the iterator is empty, [`None`] is returned.
+    /// Keeps the running Pareto front, so takes `O(n * f)` time where `f` is the size of the front.
+    #[cfg(any(test, feature = "alloc"))]
+    fn choose_pareto_optimal_by<R: Rng, F: FnMut(&Self::Item, &Self::Item) -> Option<Ordering>>(
+        self,
+        rng: &mut R,
+        dominance: F,
+    ) -> Option<Self::Item> {
+        pareto::choose_from(pareto::pareto_front(self, dominance), rng)
+    }
+
+    /// Returns a random element which is not dominated by any other element,
+    /// where larger keys are better.
+    /// An element dominates another if each of its keys is at least as large as the other's
+    /// and at least one of its keys is larger.
+    /// Every element of the Pareto front is equally likely to be chosen.
+    /// `f` is called exactly once for each element.
+    /// To prefer smaller keys, wrap them in [`core::cmp::Reverse`].
+    ///
+    /// If the iterator is empty, [`None`] is returned.
+    ///
+    /// ```
+    /// use kindness::*;
+    /// use rand::SeedableRng;
+    ///
+    /// let mut rng = rand::rngs::StdRng::seed_from_u64(123);
+    /// let candidates = [("fast", 9, 2), ("good", 3, 8), ("bad", 2, 1)];
+    /// let (name, _, _) = candidates
+    ///     .into_iter()
+    ///     .choose_pareto_optimal_by_keys(&mut rng, |(_, speed, quality)| [*speed, *quality])
+    ///     .unwrap();
+    /// assert_ne!(name, "bad");
+    /// ```
+    #[cfg(any(test, feature = "alloc"))]
+    fn choose_pareto_optimal_by_keys<K: Ord, const N: usize, R: Rng, F: FnMut(&Self::Item) -> [K; N]>(
+        self,
+        rng: &mut R,
+        mut f: F,
+    ) -> Option<Self::Item> {
+        let keyed = self.map(|item| (f(&item), item));
+        let front = pareto::pareto_front(keyed, |a, b| pareto::dominance_by_keys(&a.0, &b.0));
+        pareto::choose_from(front, rng).map(|(_, item)| item)
+    }
+
+    /// Returns the elements which are not dominated by any other element, in random order.
+    /// `dominance(a, b)` should return `Some(Greater)` if `a` dominates `b`, `Some(Less)` if `b` dominates `a`
+    /// and `Some(Equal)` or `None` if neither dominates the other.
+    #[cfg(any(test, feature = "alloc"))]
+    fn pareto_front_by<R: Rng, F: FnMut(&Self::Item, &Self::Item) -> Option<Ordering>>(
+        self,
+        rng: &mut R,
+        dominance: F,
+    ) -> alloc::vec::Vec<Self::Item> {
+        let mut front = pareto::pareto_front(self, dominance);
+        pareto::shuffle(&mut front, rng);
+        front
+    }
+
+    /// Returns the elements which are not dominated by any other element, in random order,
+    /// where larger keys are better.
+    /// See [`Kindness::choose_pareto_optimal_by_keys`].
+    #[cfg(any(test, feature = "alloc"))]
+    fn pareto_front_by_keys<K: Ord, const N: usize, R: Rng, F: FnMut(&Self::Item) -> [K; N]>(
+        self,
+        rng: &mut R,
+        mut f: F,
+    ) -> alloc::vec::Vec<Self::Item> {
+        let keyed = self.map(|item| (f(&item), item));
+        let mut front: alloc::vec::Vec<_> =
+            pareto::pareto_front(keyed, |a, b| pareto::dominance_by_keys(&a.0, &b.0))
+                .into_iter()
+                .map(|(_, item)| item)
+                .collect();
+        pareto::shuffle(&mut front, rng);
+        front
+    }
+
+    /// Returns an iterator over a random element from each run of consecutive equal elements,
+    /// like [`Vec::dedup`] but choosing uniformly from each run instead of keeping the first element.
+    ///
+    /// This is lazy, uses constant memory and does not need to allocate,
+    /// so it is better than [`Kindness::choose_unique`] when equal elements are already next to each other,
+    /// e.g. when the iterator is sorted.
+    ///
+    /// ```
+    /// use kindness::*;
+    /// use rand::SeedableRng;
+    ///
+    /// let mut rng = rand::rngs::StdRng::seed_from_u64(123);
+    /// let deduped: Vec<_> = [1, 1, 2, 3, 3, 3].into_iter().choose_dedup(&mut rng).collect();
+    /// assert_eq!(deduped, [1, 2, 3]);
+    /// ```
+    fn choose_dedup<R: Rng>(self, rng: R) -> dedup::Dedup<Self, R>
+    where
+        Self::Item: PartialEq,
+    {
+        dedup::Dedup::by_eq(self, rng)
+    }
+
+    /// Returns an iterator over a random element from each run of consecutive elements
+    /// for which `same_bucket` returns `true`.
+    /// `same_bucket` is called with the element chosen so far from the current run and the next element,
+    /// so it should be an equivalence relation.
+    ///
+    /// This is lazy, uses constant memory and does not need to allocate.
+    fn choose_dedup_by<R: Rng, F: FnMut(&Self::Item, &Self::Item) -> bool>(
+        self,
+        rng: R,
+        same_bucket: F,
+    ) -> dedup::DedupBy<Self, R, F> {
+        dedup::DedupBy::by_fn(self, rng, same_bucket)
+    }
+
+    /// Returns an iterator over a random element from each run of consecutive elements with equal keys.
+    /// `key` is called exactly once for each element.
+    ///
+    /// This is lazy, uses constant memory and does not need to allocate.
+    fn choose_dedup_by_key<R: Rng, K: PartialEq, F: FnMut(&Self::Item) -> K>(
+        self,
+        rng: R,
+        key: F,
+    ) -> dedup::DedupByKey<Self, R, F, K> {
+        dedup::DedupByKey::by_key(self, rng, key)
+    }
+
+    /// Splits the elements into two vectors, putting each element in the first independently with probability `ratio`,
+    /// e.g. to split a data set into training and test sets.
+    /// Both vectors keep the order of the iterator.
+    ///
+    /// The sizes of the parts are random; use [`Kindness::random_split_exact`] for exact sizes.
+    /// `ratio` is rounded down to a multiple of `2^-64`.
+    /// The entire iterator is consumed.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `ratio` is not between 0 and 1.
+    ///
+    /// ```
+    /// use kindness::*;
+    /// use rand::SeedableRng;
+    ///
+    /// let mut rng = rand::rngs::StdRng::seed_from_u64(123);
+    /// let (train, test) = (0..100).random_split(&mut rng, 0.8);
+    /// assert_eq!(train.len() + test.len(), 100);
+    /// ```
+    #[cfg(any(test, feature = "alloc"))]
+    fn random_split<R: Rng>(
+        self,
+        rng: &mut R,
+        ratio: f64,
+    ) -> (alloc::vec::Vec<Self::Item>, alloc::vec::Vec<Self::Item>) {
+        split::random_split(self, rng, ratio)
+    }
+
+    /// Splits the elements into two vectors, putting exactly `ratio` of them, rounded down, in the first.
+    /// Every subset of that size is equally likely to be the first part.
+    /// Both vectors keep the order of the iterator.
+    ///
+    /// If the iterator has a size hint which indicates an exact length, the elements are split as they are visited.
+    /// Otherwise they are collected first to find the length.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `ratio` is not between 0 and 1.
+    ///
+    /// ```
+    /// use kindness::*;
+    /// use rand::SeedableRng;
+    ///
+    /// let mut rng = rand::rngs::StdRng::seed_from_u64(123);
+    /// let (train, test) = (0..100).random_split_exact(&mut rng, 0.8);
+    /// assert_eq!(train.len(), 80);
+    /// assert_eq!(test.len(), 20);
+    /// ```
+    #[cfg(any(test, feature = "alloc"))]
+    fn random_split_exact<R: Rng>(
+        self,
+        rng: &mut R,
+        ratio: f64,
+    ) -> (alloc::vec::Vec<Self::Item>, alloc::vec::Vec<Self::Item>) {
+        split::random_split_exact(self, rng, ratio)
+    }
+
+    /// Splits the elements into two vectors like [`Kindness::random_split`], but stratified by `key`
+    /// so that each part gets a balanced share of every key.
+    /// Both vectors keep the order of the iterator.
+    ///
+    /// Of the `len` elements with each key, `len * ratio` are put in the first part,
+    /// rounded up with probability equal to the fractional part and otherwise down,
+    /// so each element is in the first part with probability `ratio`, rounded to a multiple of `2^-64`.
+    /// `key` is called exactly once for each element.
+    /// The elements are collected first to count each key.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `ratio` is not between 0 and 1.
+    ///
+    /// ```
+    /// use kindness::*;
+    /// use rand::SeedableRng;
+    ///
+    /// let mut rng = rand::rngs::StdRng::seed_from_u64(123);
+    /// let (train, test) = (0..100).random_split_by_key(&mut rng, 0.8, |x| x % 2);
+    /// assert_eq!(train.iter().filter(|x| *x % 2 == 0).count(), 40);
+    /// assert_eq!(test.iter().filter(|x| *x % 2 == 1).count(), 10);
+    /// ```
+    #[cfg(any(test, feature = "alloc"))]
+    fn random_split_by_key<R: Rng, K: Ord, F: FnMut(&Self::Item) -> K>(
+        self,
+        rng: &mut R,
+        ratio: f64,
+        key: F,
+    ) -> (alloc::vec::Vec<Self::Item>, alloc::vec::Vec<Self::Item>) {
+        split::random_split_by_key(self, rng, ratio, key)
+    }
+
+    /// Splits the elements into two vectors like [`Kindness::random_split_exact`], but stratified by `key`.
+    /// Of the `len` elements with each key, exactly `len * ratio`, rounded down, are put in the first part.
+    /// Every subset of those sizes is equally likely to be the first part.
+    /// Both vectors keep the order of the iterator.
+    ///
+    /// `key` is called exactly once for each element.
+    /// The elements are collected first to count each key.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `ratio` is not between 0 and 1.
+    ///
+    /// ```
+    /// use kindness::*;
+    /// use rand::SeedableRng;
+    ///
+    /// let mut rng = rand::rngs::StdRng::seed_from_u64(123);
+    /// let (train, test) = (0..10).random_split_exact_by_key(&mut rng, 0.5, |x| *x < 3);
+    /// assert_eq!(train.iter().filter(|x| **x < 3).count(), 1);
+    /// assert_eq!(test.iter().filter(|x| **x < 3).count(), 2);
+    /// assert_eq!(train.len(), 4);
+    /// ```
+    #[cfg(any(test, feature = "alloc"))]
+    fn random_split_exact_by_key<R: Rng, K: Ord, F: FnMut(&Self::Item) -> K>(
+        self,
+        rng: &mut R,
+        ratio: f64,
+        key: F,
+    ) -> (alloc::vec::Vec<Self::Item>, alloc::vec::Vec<Self::Item>) {
+        split::random_split_exact_by_key(self, rng, ratio, key)
+    }
 
-        let iter = table.into_iter();
-        unique::iterators::Unique::new(iter)
+    /// Returns an iterator which tags each element with a random fold id in `0..k`, for `k`-fold cross-validation.
+    ///
+    /// Each consecutive block of `k` elements gets every fold id exactly once, in a random order,
+    /// so each element is equally likely to be in any fold and the sizes of the folds never differ by more than one.
+    ///
+    /// This is lazy and allocates `k` fold ids.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `k` is zero.
+    ///
+    /// ```
+    /// use kindness::*;
+    /// use rand::SeedableRng;
+    ///
+    /// let mut rng = rand::rngs::StdRng::seed_from_u64(123);
+    /// let mut sizes = [0; 3];
+    /// for (fold, _) in (0..10).k_fold_assign(&mut rng, 3) {
+    ///     sizes[fold] += 1;
+    /// }
+    /// sizes.sort();
+    /// assert_eq!(sizes, [3, 3, 4]);
+    /// ```
+    #[cfg(any(test, feature = "alloc"))]
+    fn k_fold_assign<R: Rng>(self, rng: R, k: usize) -> split::KFoldAssign<Self, R> {
+        split::KFoldAssign::new(self, rng, k)
+    }
 
+    /// Returns an iterator which tags each element with a random fold id in `0..k`,
+    /// stratified by `key` so that each fold gets a balanced share of every key.
+    ///
+    /// Within the elements with each key, each consecutive block of `k` elements gets every fold id exactly once,
+    /// so the number of elements with that key in each fold never differs by more than one.
+    /// `key` is called exactly once for each element.
+    ///
+    /// This is lazy and allocates `k` fold ids for each distinct key.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `k` is zero.
+    #[cfg(any(test, feature = "alloc"))]
+    fn k_fold_assign_by_key<R: Rng, K: Ord, F: FnMut(&Self::Item) -> K>(
+        self,
+        rng: R,
+        k: usize,
+        key: F,
+    ) -> split::KFoldAssignByKey<Self, R, F, K> {
+        split::KFoldAssignByKey::new(self, rng, k, key)
+    }
 
+    /// Returns an iterator which shuffles the elements through a buffer of `capacity` elements,
+    /// like the shuffle buffer of `tf.data`, for streams which are too long to collect.
+    ///
+    /// Once the buffer is full, each new element replaces a uniformly random buffered element, which is returned.
+    /// When the iterator ends, the rest of the buffer is returned in random order.
+    /// If `capacity` is at least the number of elements, the result is a uniformly random permutation;
+    /// otherwise elements can only move `capacity - 1` places earlier.
+    ///
+    /// This is lazy and holds at most `capacity` elements.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `capacity` is zero.
+    ///
+    /// ```
+    /// use kindness::*;
+    /// use rand::SeedableRng;
+    ///
+    /// let mut rng = rand::rngs::StdRng::seed_from_u64(123);
+    /// let first: Vec<_> = (0..).shuffle_buffered(&mut rng, 100).take(10).collect();
+    /// assert!(first.iter().all(|&x| x < 110));
+    /// ```
+    #[cfg(any(test, feature = "alloc"))]
+    fn shuffle_buffered<R: Rng>(self, rng: R, capacity: usize) -> shuffle::ShuffleBuffered<Self, R> {
+        shuffle::ShuffleBuffered::new(self, rng, capacity)
+    }
+
+    /// Returns an iterator over unique elements of this iterator.
+    /// Elements are chosen randomly from the duplicates.
+    /// Duplicates are detected using hash and equality.
+    /// Uses the global allocator and the default hasher which is safe from HashDos attacks.
+    #[cfg(any(test, all(feature = "hashbrown", feature = "std")))]
+    fn choose_unique<R: Rng>(
+        mut self,
+        rng: &mut R,
+    ) -> unique::iterators::Unique<Self::Item, allocator_api2::alloc::Global>
+    where
+        Self::Item: Hash + Eq,
+    {
+        let hash_builder = std::collections::hash_map::RandomState::new();
+        let alloc = allocator_api2::alloc::Global;
+        self.choose_unique_with_hasher_in(rng, hash_builder, alloc)
+    }
+
+    /// Returns an iterator over unique elements of this iterator.
+    /// Elements are chosen randomly from the duplicates.
+    /// Duplicates are detected using hash and equality.
+    ///
+    /// You must supply a `BuildHasher` and an `Allocator` to use this.
+    /// The `std` feature provides a more ergonomic `choose_unique`
+    #[cfg(any(test, all(feature = "hashbrown")))]
+    fn choose_unique_with_hasher_in<
+        R: Rng,
+        S: BuildHasher,
+        A: allocator_api2::alloc::Allocator + Clone,
+    >(
+        mut self,
+        rng: &mut R,
+        hash_builder: S,
+        alloc: A,
+    ) -> unique::iterators::Unique<Self::Item, A>
+    where
+        Self::Item: Hash + Eq,
+    {
+        choose_unique_with_hasher_in::<Self, R, S, A, usize>(self, rng, hash_builder, alloc)
     }
+    /// Returns the unique elements of this iterator in an [`array_vec::ArrayVec`], in the order they first appear.
+    /// Elements are chosen randomly from the duplicates, with the same distribution as [`Kindness::choose_unique`].
+    /// Duplicates are detected using equality, with a linear search, so this is best for small `N`.
+    ///
+    /// This does not need an allocator or a hasher.
+    /// Returns a [`array_vec::CapacityError`] as soon as there are more than `N` unique elements.
+    ///
+    /// ```
+    /// use kindness::*;
+    /// use rand::SeedableRng;
+    ///
+    /// let mut rng = rand::rngs::StdRng::seed_from_u64(123);
+    /// let unique = [3, 1, 3, 2, 1].into_iter().choose_unique_array::<4, _>(&mut rng).unwrap();
+    /// assert!(unique.into_iter().eq([3, 1, 2]));
+    /// assert!([1, 2, 3].into_iter().choose_unique_array::<2, _>(&mut rng).is_err());
+    /// ```
+    fn choose_unique_array<const N: usize, R: Rng>(
+        self,
+        rng: &mut R,
+    ) -> Result<array_vec::ArrayVec<Self::Item, N>, array_vec::CapacityError>
+    where
+        Self::Item: Eq,
+    {
+        choose_unique_array::<Self, R, usize, N>(self, rng)
+    }
+
     /// Returns an iterator over unique elements of this iterator.    
     /// Elements are chosen randomly from the duplicates.
     /// Duplicates are detected by comparing the key they map to with the keying function `get_key` by hash and equality.
@@ -424,83 +1581,393 @@ where
         hash_builder: S,
         alloc: A,
     ) -> unique::iterators::UniqueByKey<K, Self::Item, A> {
-        use hashbrown::{hash_map::Entry, HashMap};
-        let mut map: HashMap<K, (Self::Item, usize), S, A> =
-            HashMap::with_hasher_in(hash_builder, alloc);
-        let mut coin_flipper = CoinFlipper::new(rng);
-        for element in self {
-            let v = get_key(&element);
-            let entry = map.entry(v).and_modify(|(e, c)| *c += 1);
-
-            match entry {
-                Entry::Occupied(mut occupied) => {
-                    let (previous, new_count) = occupied.get_mut();
-                    if coin_flipper.gen_ratio_one_over(*new_count) {
-                        *previous = element;
-                    }
-                }
-                Entry::Vacant(vacant) => {
-                    vacant.insert((element, 1));
-                }
-            }
-        }
+        choose_unique_by_key_with_hasher_in::<Self, R, K, F, S, A, usize>(
+            self,
+            rng,
+            get_key,
+            hash_builder,
+            alloc,
+        )
+    }
+
+    /// Like [`Kindness::choose_unique_by_key`] but for an iterator of [`Result`]s and a fallible keying function.
+    /// Returns the first error, from either the iterator or `get_key`, if there is one.
+    /// `get_key` is not called again after an error.
+    #[cfg(any(test, all(feature = "hashbrown", feature = "std")))]
+    fn try_choose_unique_by_key<T, E, R: Rng, K: Eq + Hash, F: FnMut(&T) -> Result<K, E>>(
+        self,
+        rng: &mut R,
+        get_key: F,
+    ) -> Result<unique::iterators::UniqueByKey<K, T, allocator_api2::alloc::Global>, E>
+    where
+        Self: Iterator<Item = Result<T, E>>,
+    {
+        let hash_builder = std::collections::hash_map::RandomState::new();
+        let alloc = allocator_api2::alloc::Global;
+        self.try_choose_unique_by_key_with_hasher_in(rng, get_key, hash_builder, alloc)
+    }
+
+    /// Like [`Kindness::choose_unique_by_key_with_hasher_in`] but for an iterator of [`Result`]s and a fallible keying function.
+    /// Returns the first error, from either the iterator or `get_key`, if there is one.
+    /// `get_key` is not called again after an error.
+    #[cfg(any(test, feature = "hashbrown"))]
+    fn try_choose_unique_by_key_with_hasher_in<
+        T,
+        E,
+        R: Rng,
+        K: Eq + Hash,
+        F: FnMut(&T) -> Result<K, E>,
+        S: BuildHasher,
+        A: allocator_api2::alloc::Allocator + Clone,
+    >(
+        self,
+        rng: &mut R,
+        get_key: F,
+        hash_builder: S,
+        alloc: A,
+    ) -> Result<unique::iterators::UniqueByKey<K, T, A>, E>
+    where
+        Self: Iterator<Item = Result<T, E>>,
+    {
+        fallible::try_with(keyed(self, get_key), |shunt| {
+            choose_unique_keyed_with_hasher_in::<_, _, R, K, S, A, usize>(
+                shunt,
+                rng,
+                hash_builder,
+                alloc,
+            )
+        })
+    }
+
+    /// Returns a random element of the most frequent value.
+    /// If several values are equally frequent, one of them is chosen uniformly at random,
+    /// and then one of its duplicates is chosen uniformly at random.
+    /// Duplicates are detected using hash and equality.
+    /// Uses the global allocator and the default hasher which is safe from HashDos attacks.
+    ///
+    /// If the iterator is empty, [`None`] is returned.
+    ///
+    /// ```
+    /// use kindness::*;
+    /// use rand::SeedableRng;
+    ///
+    /// let mut rng = rand::rngs::StdRng::seed_from_u64(123);
+    /// let mode = ["cat", "dog", "cat", "bird"].into_iter().choose_mode(&mut rng);
+    /// assert_eq!(mode, Some("cat"));
+    /// ```
+    #[cfg(any(test, all(feature = "hashbrown", feature = "std")))]
+    fn choose_mode<R: Rng>(self, rng: &mut R) -> Option<Self::Item>
+    where
+        Self::Item: Hash + Eq,
+    {
+        let hash_builder = std::collections::hash_map::RandomState::new();
+        let alloc = allocator_api2::alloc::Global;
+        self.choose_mode_with_hasher_in(rng, hash_builder, alloc)
+    }
+
+    /// Returns a random element of the most frequent value.
+    /// If several values are equally frequent, one of them is chosen uniformly at random,
+    /// and then one of its duplicates is chosen uniformly at random.
+    /// Duplicates are detected using hash and equality.
+    ///
+    /// You must supply a `BuildHasher` and an `Allocator` to use this.
+    /// The `std` feature provides a more ergonomic `choose_mode`
+    #[cfg(any(test, feature = "hashbrown"))]
+    fn choose_mode_with_hasher_in<
+        R: Rng,
+        S: BuildHasher,
+        A: allocator_api2::alloc::Allocator + Clone,
+    >(
+        self,
+        rng: &mut R,
+        hash_builder: S,
+        alloc: A,
+    ) -> Option<Self::Item>
+    where
+        Self::Item: Hash + Eq,
+    {
+        choose_mode_with_hasher_in::<Self, R, S, A, usize>(self, rng, hash_builder, alloc)
+    }
+
+    /// Returns a random element whose key is the most frequent key.
+    /// If several keys are equally frequent, one of them is chosen uniformly at random,
+    /// and then one of the elements with that key is chosen uniformly at random.
+    /// Keys are compared by hash and equality.
+    /// `get_key` is called exactly once for each element.
+    ///
+    /// If the iterator is empty, [`None`] is returned.
+    #[cfg(any(test, all(feature = "hashbrown", feature = "std")))]
+    fn choose_mode_by_key<R: Rng, K: Eq + Hash, F: FnMut(&Self::Item) -> K>(
+        self,
+        rng: &mut R,
+        get_key: F,
+    ) -> Option<Self::Item> {
+        let hash_builder = std::collections::hash_map::RandomState::new();
+        let alloc = allocator_api2::alloc::Global;
+        self.choose_mode_by_key_with_hasher_in(rng, get_key, hash_builder, alloc)
+    }
 
+    /// Returns a random element whose key is the most frequent key.
+    /// If several keys are equally frequent, one of them is chosen uniformly at random,
+    /// and then one of the elements with that key is chosen uniformly at random.
+    /// Keys are compared by hash and equality.
+    /// `get_key` is called exactly once for each element.
+    ///
+    /// You must supply a `BuildHasher` and an `Allocator` to use this.
+    /// The `std` feature provides a more ergonomic `choose_mode_by_key`
+    #[cfg(any(test, feature = "hashbrown"))]
+    fn choose_mode_by_key_with_hasher_in<
+        R: Rng,
+        K: Eq + Hash,
+        F: FnMut(&Self::Item) -> K,
+        S: BuildHasher,
+        A: allocator_api2::alloc::Allocator + Clone,
+    >(
+        self,
+        rng: &mut R,
+        get_key: F,
+        hash_builder: S,
+        alloc: A,
+    ) -> Option<Self::Item> {
+        choose_mode_by_key_with_hasher_in::<Self, R, K, F, S, A, usize>(
+            self,
+            rng,
+            get_key,
+            hash_builder,
+            alloc,
+        )
+    }
 
+    /// Counts elements with `C` instead of `usize`.
+    ///
+    /// The methods on the returned [`wide::WideCounter`] behave like the methods on [`Kindness`]
+    /// but stay exactly uniform for iterators with more than `usize::MAX` elements.
+    /// `u64` is enough for any realistic stream, even on 32-bit targets.
+    ///
+    /// ```
+    /// use kindness::*;
+    /// use rand::SeedableRng;
+    ///
+    /// let mut rng = rand::rngs::StdRng::seed_from_u64(123);
+    /// let m = [3, 2, 1, 2, 3].iter().wide_counter::<u64>().choose_max(&mut rng).unwrap();
+    /// assert_eq!(*m, 3)
+    /// ```
+    fn wide_counter<C: Counter>(self) -> wide::WideCounter<Self, C> {
+        wide::WideCounter::new(self)
+    }
 
-        unique::iterators::UniqueByKey::new(map.into_values())
+    /// Collects the elements into an [`alias::AliasTable`] which draws elements with probability
+    /// proportional to `weight` in constant time.
+    /// `weight` is called exactly once for each element.
+    /// Elements with zero weight are dropped.
+    ///
+    /// Returns [`None`] if no element has a positive weight.
+    /// Panics if more than `u32::MAX` elements have positive weights.
+    ///
+    /// ```
+    /// use kindness::*;
+    /// use rand::SeedableRng;
+    ///
+    /// let mut rng = rand::rngs::StdRng::seed_from_u64(123);
+    /// let table = ["common", "rare"].into_iter().collect_alias_table(|x| if *x == "rare" { 1 } else { 99 }).unwrap();
+    /// let commons = table.sample_iter(&mut rng).take(1000).filter(|x| **x == "common").count();
+    /// assert!(commons > 950);
+    /// ```
+    #[cfg(any(test, feature = "alloc"))]
+    fn collect_alias_table<F: FnMut(&Self::Item) -> u64>(
+        self,
+        weight: F,
+    ) -> Option<alias::AliasTable<Self::Item>> {
+        alias::AliasTable::new(self, weight)
+    }
+
+    /// Collects the elements into a [`dynamic_weighted::DynamicWeightedIndex`] which draws elements with probability
+    /// proportional to their weight and allows weights to be changed later.
+    /// `weight` is called exactly once for each element.
+    /// The key of each element is its position in the iterator.
+    ///
+    /// ```
+    /// use kindness::*;
+    /// use rand::SeedableRng;
+    ///
+    /// let mut rng = rand::rngs::StdRng::seed_from_u64(123);
+    /// let mut index = ["a", "b", "c"].into_iter().collect_dynamic_weighted(|_| 1);
+    /// index.update_weight(0, 0);
+    /// index.remove(1);
+    /// assert_eq!(index.sample(&mut rng), Some(2));
+    /// ```
+    #[cfg(any(test, feature = "alloc"))]
+    fn collect_dynamic_weighted<F: FnMut(&Self::Item) -> u64>(
+        self,
+        weight: F,
+    ) -> dynamic_weighted::DynamicWeightedIndex<Self::Item> {
+        dynamic_weighted::DynamicWeightedIndex::from_iter(self, weight)
+    }
+
+    /// Collects the elements into a [`bootstrap::Bootstrap`] which lazily draws `replicates` bootstrap replicates,
+    /// each of which is `n` elements drawn uniformly with replacement from the `n` elements of the iterator.
+    ///
+    /// Each replicate can be read as the multiplicity of each element, which follows a multinomial distribution,
+    /// or as an iterator over the indices of the draws.
+    /// Indices are drawn in batches with [`uniform::Uniform::fill`], which packs several into each random number,
+    /// and only one replicate is held at a time.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there are more than `u32::MAX` elements.
+    ///
+    /// ```
+    /// use kindness::*;
+    /// use rand::SeedableRng;
+    ///
+    /// let mut rng = rand::rngs::StdRng::seed_from_u64(123);
+    /// let mut bootstrap = [1.0, 2.0, 4.0, 8.0].into_iter().bootstrap(&mut rng, 1000);
+    /// let mut means = Vec::new();
+    /// while let Some(replicate) = bootstrap.next_replicate() {
+    ///     means.push(replicate.iter().sum::<f64>() / 4.0);
+    /// }
+    /// assert_eq!(means.len(), 1000);
+    /// ```
+    #[cfg(any(test, feature = "alloc"))]
+    fn bootstrap<R: Rng>(self, rng: R, replicates: usize) -> bootstrap::Bootstrap<Self::Item, R> {
+        bootstrap::Bootstrap::new(self.collect(), rng, replicates)
     }
 }
 
-// Sample a number uniformly between 0 and `ubound`. Uses 32-bit sampling where
-// possible, primarily in order to produce the same output on 32-bit and 64-bit
-// platforms.
-#[inline]
-fn gen_index<R: Rng + ?Sized>(rng: &mut R, ubound: usize) -> usize {
-    if ubound <= (core::u32::MAX as usize) {
-        rng.gen_range(0..ubound as u32) as usize
-    } else {
-        rng.gen_range(0..ubound)
+#[cfg(test)]
+mod tests {
+    use core::{
+        hash::{BuildHasherDefault, Hash},
+        ops::Range,
+    };
+    use std::hash::DefaultHasher;
+
+    use crate::{Kindness, UniqueChooser};
+    use rand::{Rng, RngCore, SeedableRng, rngs::StdRng};
+
+    const RUNS: usize = 10000;
+    const LENGTH: usize = 100;
+    const LOWER_TOLERANCE: usize = 60;
+    const UPPER_TOLERANCE: usize = 140;
+
+    #[test]
+    fn test_choose_max_empty(){
+        let vec: Vec<u8> = vec![];
+        let mut rng = get_rng();
+        let r = vec.into_iter().choose_max(&mut rng);
+        assert_eq!(r, None)        
+    }
+    
+    #[test]
+    fn test_choose_max_by_key_empty(){
+        let vec: Vec<u8> = vec![];
+        let mut rng = get_rng();
+        let r = vec.into_iter().choose_max_by_key(&mut rng, |x|*x);
+        assert_eq!(r, None)        
+    }
+
+    #[test]
+    fn test_choose_unique() {
+        let mut counts: [usize; LENGTH] = [0; LENGTH];
+        let mut rng = get_rng();
+
+        for _ in 0..RUNS {
+            let range = (0..LENGTH).map(RoughNumber);
+            let elements = range.choose_unique(&mut rng);
+
+            for x in elements {
+                counts[x.0] += 1;
+            }
+        }
+
+        insta::assert_debug_snapshot!(counts);
+        for x in counts {
+            assert!(x > LOWER_TOLERANCE * 10);
+            assert!(x < UPPER_TOLERANCE * 10);
+        }
+    }
+
+    fn fixed_unique_chooser() -> UniqueChooser<RoughNumber, BuildHasherDefault<DefaultHasher>> {
+        UniqueChooser::new_in(BuildHasherDefault::default(), allocator_api2::alloc::Global)
+    }
+
+    #[test]
+    fn test_unique_chooser_extend() {
+        let mut counts: [usize; LENGTH] = [0; LENGTH];
+        let mut rng = get_rng();
+        let mut chooser = fixed_unique_chooser();
+
+        for _ in 0..RUNS {
+            chooser.clear();
+            // The batches split some sets of duplicates
+            chooser.extend((0..35).map(RoughNumber), &mut rng);
+            chooser.extend((35..72).map(RoughNumber), &mut rng);
+            chooser.extend((72..LENGTH).map(RoughNumber), &mut rng);
+            assert_eq!(chooser.len(), 10);
+
+            for x in chooser.drain() {
+                counts[x.0] += 1;
+            }
+            assert!(chooser.is_empty());
+        }
+
+        insta::assert_debug_snapshot!(counts);
+        for x in counts {
+            assert!(x > LOWER_TOLERANCE * 10);
+            assert!(x < UPPER_TOLERANCE * 10);
+        }
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use core::{hash::Hash, ops::Range};
+    #[test]
+    fn test_unique_chooser_merge() {
+        let mut counts: [usize; LENGTH] = [0; LENGTH];
+        let mut rng = get_rng();
 
-    use crate::Kindness;
-    use rand::{Rng, RngCore, SeedableRng, rngs::StdRng};
+        for _ in 0..RUNS {
+            let mut chooser = fixed_unique_chooser();
+            chooser.extend((0..35).map(RoughNumber), &mut rng);
+            let mut other = fixed_unique_chooser();
+            other.extend((35..LENGTH).map(RoughNumber), &mut rng);
+            chooser.merge(other, &mut rng);
 
-    const RUNS: usize = 10000;
-    const LENGTH: usize = 100;
-    const LOWER_TOLERANCE: usize = 60;
-    const UPPER_TOLERANCE: usize = 140;
+            for x in chooser {
+                counts[x.0] += 1;
+            }
+        }
 
-    #[test]
-    fn test_choose_max_empty(){
-        let vec: Vec<u8> = vec![];
-        let mut rng = get_rng();
-        let r = vec.into_iter().choose_max(&mut rng);
-        assert_eq!(r, None)        
+        insta::assert_debug_snapshot!(counts);
+        for x in counts {
+            assert!(x > LOWER_TOLERANCE * 10);
+            assert!(x < UPPER_TOLERANCE * 10);
+        }
     }
-    
+
     #[test]
-    fn test_choose_max_by_key_empty(){
-        let vec: Vec<u8> = vec![];
+    fn test_unique_chooser_iter() {
         let mut rng = get_rng();
-        let r = vec.into_iter().choose_max_by_key(&mut rng, |x|*x);
-        assert_eq!(r, None)        
+        let mut chooser = UniqueChooser::new();
+        chooser.extend([1, 2, 2, 3], &mut rng);
+        let mut other = UniqueChooser::new();
+        other.extend([3, 4], &mut rng);
+        chooser.merge(other, &mut rng);
+
+        let mut elements: Vec<_> = chooser.iter().copied().collect();
+        elements.sort_unstable();
+        assert_eq!(elements, [1, 2, 3, 4]);
+        assert_eq!(chooser.iter().len(), 4);
     }
 
     #[test]
-    fn test_choose_unique() {
+    fn test_choose_unique_array() {
         let mut counts: [usize; LENGTH] = [0; LENGTH];
         let mut rng = get_rng();
 
         for _ in 0..RUNS {
             let range = (0..LENGTH).map(RoughNumber);
-            let elements = range.choose_unique(&mut rng);
+            let elements = range.choose_unique_array::<10, _>(&mut rng).unwrap();
 
-            for x in elements {
+            for (i, x) in elements.into_iter().enumerate() {
+                assert_eq!(x.0 / 10, i);
                 counts[x.0] += 1;
             }
         }
@@ -512,6 +1979,39 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_choose_unique_array_matches_choose_unique() {
+        for seed in 0..32 {
+            let range = (0..LENGTH).map(|x| RoughNumber((x * 37) % LENGTH));
+            let array: Vec<_> = range
+                .clone()
+                .choose_unique_array::<10, _>(&mut StdRng::seed_from_u64(seed))
+                .unwrap()
+                .into_iter()
+                .map(|x| x.0)
+                .collect();
+            let mut unique: Vec<_> = range
+                .choose_unique(&mut StdRng::seed_from_u64(seed))
+                .map(|x| x.0)
+                .collect();
+            unique.sort_unstable_by_key(|x| array.iter().position(|y| y / 10 == x / 10));
+            assert_eq!(array, unique);
+        }
+    }
+
+    #[test]
+    fn test_choose_unique_array_capacity() {
+        let mut rng = get_rng();
+        let mut consumed = 0;
+        let result = (0..LENGTH)
+            .inspect(|_| consumed += 1)
+            .choose_unique_array::<10, _>(&mut rng);
+        assert_eq!(result, Err(crate::array_vec::CapacityError));
+        // Stops at the first element which does not fit
+        assert_eq!(consumed, 11);
+        assert!((0..0).choose_unique_array::<0, _>(&mut rng).unwrap().is_empty());
+    }
+
     #[test]
     fn test_choose_unique_by_key() {
         let mut counts: [usize; LENGTH] = [0; LENGTH];
@@ -560,7 +2060,7 @@ mod tests {
             assert!(x < UPPER_TOLERANCE);
         }
 
-        assert_contains(RUNS..(RUNS * 2), &rng.count); // There should be at most two calls per iteration because we are using gen_range only once
+        assert_contains(RUNS..(RUNS * 2), &rng.count); // There should be at most two calls per iteration because we only draw one index
     }
 
     #[test]
@@ -609,6 +2109,28 @@ mod tests {
         //assert_contains(RUNS..2000000, &rng.count);
     }
 
+    #[test]
+    fn test_random_element_window_hinted() {
+        let mut counts: [usize; LENGTH] = [0; LENGTH];
+        let mut rng = get_rng();
+
+        for _ in 0..RUNS {
+            let range = WindowHintedIterator(0..LENGTH, 10);
+            assert_eq!((10, None), range.size_hint());
+            let element = range.choose_item(&mut rng).unwrap();
+            counts[element] += 1;
+        }
+
+        insta::assert_debug_snapshot!(counts);
+
+        for x in counts {
+            assert!(x > LOWER_TOLERANCE);
+            assert!(x < UPPER_TOLERANCE);
+        }
+
+        assert_contains(RUNS..(RUNS * 4), &rng.count); // Index draws share random bits with each other and with the coin flips
+    }
+
     #[test]
     fn test_random_max() {
         let mut counts: [usize; LENGTH] = [0; LENGTH];
@@ -767,6 +2289,331 @@ mod tests {
         //assert_contains(0..200000, &rng.count);
     }
 
+    #[test]
+    fn test_choose_multiple_with_size_hint() {
+        let mut counts: [usize; LENGTH] = [0; LENGTH];
+        let mut rng = get_rng();
+
+        for _ in 0..RUNS {
+            let range = 0..LENGTH;
+            let elements = range.choose_multiple(&mut rng, 10);
+            assert_eq!(elements.len(), 10);
+            assert!(elements.windows(2).all(|w| w[0] < w[1]));
+            for x in elements {
+                counts[x] += 1;
+            }
+        }
+
+        insta::assert_debug_snapshot!(counts);
+        for x in counts {
+            assert!(x > LOWER_TOLERANCE * 10);
+            assert!(x < UPPER_TOLERANCE * 10);
+        }
+    }
+
+    #[test]
+    fn test_choose_multiple_unhinted() {
+        let mut counts: [usize; LENGTH] = [0; LENGTH];
+        let mut rng = get_rng();
+
+        for _ in 0..RUNS {
+            let range = UnhintedIterator(0..LENGTH);
+            let mut elements = range.choose_multiple(&mut rng, 10);
+            elements.sort_unstable();
+            elements.dedup();
+            assert_eq!(elements.len(), 10);
+            for x in elements {
+                counts[x] += 1;
+            }
+        }
+
+        insta::assert_debug_snapshot!(counts);
+        for x in counts {
+            assert!(x > LOWER_TOLERANCE * 10);
+            assert!(x < UPPER_TOLERANCE * 10);
+        }
+    }
+
+    #[test]
+    fn test_choose_multiple_large_amount() {
+        // Half of a large exact size iterator; this used to take quadratic time
+        let mut rng = get_rng();
+        let sample = (0..1_000_000).choose_multiple(&mut rng, 500_000);
+        assert_eq!(sample.len(), 500_000);
+        assert!(sample.windows(2).all(|w| w[0] < w[1]));
+    }
+
+    #[test]
+    fn test_choose_multiple_insufficient() {
+        let mut rng = get_rng();
+        assert_eq!((0..5).choose_multiple(&mut rng, 10), [0, 1, 2, 3, 4]);
+        assert_eq!(UnhintedIterator(0..5).choose_multiple(&mut rng, 10), [0, 1, 2, 3, 4]);
+        assert!((0..5).choose_multiple(&mut rng, 0).is_empty());
+        assert!(UnhintedIterator(0..5).choose_multiple(&mut rng, 0).is_empty());
+    }
+
+    #[test]
+    fn test_choose_multiple_ordered_with_size_hint() {
+        let mut counts: [usize; LENGTH] = [0; LENGTH];
+        let mut rng = get_rng();
+
+        for _ in 0..RUNS {
+            let range = 0..LENGTH;
+            let elements = range.choose_multiple_ordered(&mut rng, 10);
+            assert_eq!(elements.len(), 10);
+            assert!(elements.windows(2).all(|w| w[0] < w[1]));
+            for x in elements {
+                counts[x] += 1;
+            }
+        }
+
+        insta::assert_debug_snapshot!(counts);
+        for x in counts {
+            assert!(x > LOWER_TOLERANCE * 10);
+            assert!(x < UPPER_TOLERANCE * 10);
+        }
+    }
+
+    #[test]
+    fn test_choose_multiple_ordered_sparse() {
+        // Choosing 5 of 100 uses Method D until the last element
+        let mut counts: [usize; LENGTH] = [0; LENGTH];
+        let mut rng = get_rng();
+
+        for _ in 0..RUNS {
+            let elements = (0..LENGTH).choose_multiple_ordered(&mut rng, 5);
+            assert_eq!(elements.len(), 5);
+            assert!(elements.windows(2).all(|w| w[0] < w[1]));
+            for x in elements {
+                counts[x] += 1;
+            }
+        }
+
+        insta::assert_debug_snapshot!(counts);
+        for x in counts {
+            assert!(x > LOWER_TOLERANCE * 5);
+            assert!(x < UPPER_TOLERANCE * 5);
+        }
+    }
+
+    #[test]
+    fn test_choose_multiple_ordered_random_numbers_used() {
+        // Method D needs a constant expected number of random numbers for each chosen element,
+        // however many elements are skipped
+        let mut rng = get_rng();
+        let elements = (0..usize::MAX).choose_multiple_ordered(&mut rng, 100);
+        assert_eq!(elements.len(), 100);
+        assert!(elements.windows(2).all(|w| w[0] < w[1]));
+        assert_contains(100..400, &rng.count);
+    }
+
+    #[test]
+    fn test_choose_multiple_ordered_unhinted() {
+        let mut counts: [usize; LENGTH] = [0; LENGTH];
+        let mut rng = get_rng();
+
+        for _ in 0..RUNS {
+            let range = UnhintedIterator(0..LENGTH);
+            let elements = range.choose_multiple_ordered(&mut rng, 10);
+            assert_eq!(elements.len(), 10);
+            assert!(elements.windows(2).all(|w| w[0] < w[1]));
+            for x in elements {
+                counts[x] += 1;
+            }
+        }
+
+        insta::assert_debug_snapshot!(counts);
+        for x in counts {
+            assert!(x > LOWER_TOLERANCE * 10);
+            assert!(x < UPPER_TOLERANCE * 10);
+        }
+    }
+
+    #[test]
+    fn test_choose_multiple_ordered_pairs() {
+        // Every pair should be equally likely
+        let mut counts = [[0usize; 5]; 5];
+        let mut rng = get_rng();
+        for _ in 0..RUNS {
+            let pair = (0..5).choose_multiple_ordered(&mut rng, 2);
+            counts[pair[0]][pair[1]] += 1;
+        }
+        for a in 0..5 {
+            for b in 0..5 {
+                if a < b {
+                    assert!(counts[a][b] > RUNS / 10 * 9 / 10, "{a},{b}: {}", counts[a][b]);
+                    assert!(counts[a][b] < RUNS / 10 * 11 / 10, "{a},{b}: {}", counts[a][b]);
+                } else {
+                    assert_eq!(counts[a][b], 0);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_choose_multiple_ordered_insufficient() {
+        let mut rng = get_rng();
+        assert_eq!((0..5).choose_multiple_ordered(&mut rng, 10), [0, 1, 2, 3, 4]);
+        assert_eq!((0..5).choose_multiple_ordered(&mut rng, 5), [0, 1, 2, 3, 4]);
+        assert_eq!(
+            UnhintedIterator(0..5).choose_multiple_ordered(&mut rng, 10),
+            [0, 1, 2, 3, 4]
+        );
+        assert!((0..5).choose_multiple_ordered(&mut rng, 0).is_empty());
+        assert!(UnhintedIterator(0..5).choose_multiple_ordered(&mut rng, 0).is_empty());
+    }
+
+    #[test]
+    fn test_choose_multiple_array() {
+        let mut counts: [usize; LENGTH] = [0; LENGTH];
+        let mut rng = get_rng();
+
+        for _ in 0..RUNS {
+            let range = 0..LENGTH;
+            let elements = range.choose_multiple_array::<10, _>(&mut rng);
+            assert!(elements.is_full());
+            for &x in elements.iter() {
+                counts[x] += 1;
+            }
+        }
+
+        insta::assert_debug_snapshot!(counts);
+        for x in counts {
+            assert!(x > LOWER_TOLERANCE * 10);
+            assert!(x < UPPER_TOLERANCE * 10);
+        }
+    }
+
+    #[test]
+    fn test_choose_multiple_array_matches_choose_multiple() {
+        for seed in 0..32 {
+            let array = UnhintedIterator(0..LENGTH)
+                .choose_multiple_array::<10, _>(&mut StdRng::seed_from_u64(seed));
+            let vec =
+                UnhintedIterator(0..LENGTH).choose_multiple(&mut StdRng::seed_from_u64(seed), 10);
+            assert!(array.into_iter().eq(vec));
+        }
+    }
+
+    #[test]
+    fn test_choose_multiple_array_insufficient() {
+        let mut rng = get_rng();
+        assert!((0..5)
+            .choose_multiple_array::<10, _>(&mut rng)
+            .into_iter()
+            .eq(0..5));
+        assert!((0..5).choose_multiple_array::<0, _>(&mut rng).is_empty());
+    }
+
+    #[test]
+    fn test_choose_mode() {
+        let mut counts: [usize; LENGTH] = [0; LENGTH];
+        let mut rng = get_rng();
+
+        // 20..30 and 70..80 appear twice as often as the other tens
+        let tens = |x: &usize| x / 10 == 2 || x / 10 == 7;
+        for _ in 0..RUNS {
+            let range = (0..LENGTH).chain((0..LENGTH).filter(tens)).map(RoughNumber);
+            let mode = range.choose_mode(&mut rng).unwrap();
+            counts[mode.0] += 1;
+        }
+
+        // No snapshot because the random hasher decides the order in which tied keys are considered
+        for (i, &x) in counts.iter().enumerate() {
+            if i / 10 == 2 || i / 10 == 7 {
+                // Each tens is chosen half the time, and then each of its ten numbers with equal probability
+                assert!(x > LOWER_TOLERANCE * 5);
+                assert!(x < UPPER_TOLERANCE * 5);
+            } else {
+                assert_eq!(x, 0);
+            }
+        }
+    }
+
+    #[test]
+    fn test_choose_mode_by_key() {
+        let mut counts: [usize; LENGTH] = [0; LENGTH];
+        let mut rng = get_rng();
+
+        for _ in 0..RUNS {
+            // 0..10 has one more element than any other tens
+            let mode = (0..LENGTH)
+                .chain(core::iter::once(0))
+                .choose_mode_by_key(&mut rng, |x| x / 10)
+                .unwrap();
+            counts[mode] += 1;
+        }
+
+        // No snapshot because the random hasher decides the order in which tied keys are considered
+        for (i, &x) in counts.iter().enumerate() {
+            if i >= 10 {
+                assert_eq!(x, 0);
+            } else {
+                // 0 appears twice among the eleven elements of the mode
+                let expected = if i == 0 { RUNS * 2 / 11 } else { RUNS / 11 };
+                assert!(x * 100 > expected * LOWER_TOLERANCE);
+                assert!(x * 100 < expected * UPPER_TOLERANCE);
+            }
+        }
+    }
+
+    #[test]
+    fn test_choose_mode_empty() {
+        let mut rng = get_rng();
+        assert_eq!((0..0usize).choose_mode(&mut rng), None);
+        assert_eq!((0..0usize).choose_mode_by_key(&mut rng, |x| *x), None);
+    }
+
+    #[test]
+    fn test_with_stats() {
+        let mut rng = get_rng();
+        let key = |x: &usize| x / 10;
+        let compare = |a: &usize, b: &usize| key(a).cmp(&key(b));
+
+        let chosen = (0..LENGTH).choose_max_by_key_with_stats(&mut rng, key).unwrap();
+        assert!(chosen.item >= 90);
+        assert_eq!((chosen.key, chosen.ties, chosen.total_seen), (9, 10, LENGTH));
+
+        let chosen = (0..LENGTH).choose_min_by_key_with_stats(&mut rng, key).unwrap();
+        assert!(chosen.item < 10);
+        assert_eq!((chosen.key, chosen.ties, chosen.total_seen), (0, 10, LENGTH));
+
+        let chosen = (0..LENGTH).choose_max_by_with_stats(&mut rng, compare).unwrap();
+        assert!(chosen.item >= 90);
+        assert_eq!((chosen.ties, chosen.total_seen), (10, LENGTH));
+
+        let chosen = (0..LENGTH).choose_min_by_with_stats(&mut rng, compare).unwrap();
+        assert!(chosen.item < 10);
+        assert_eq!((chosen.ties, chosen.total_seen), (10, LENGTH));
+
+        let chosen = (0..LENGTH).map(RoughNumber).choose_max_with_stats(&mut rng).unwrap();
+        assert!(chosen.item.0 >= 90);
+        assert_eq!((chosen.ties, chosen.total_seen), (10, LENGTH));
+
+        let chosen = (0..LENGTH).choose_min_with_stats(&mut rng).unwrap();
+        assert_eq!((chosen.item, chosen.ties, chosen.total_seen), (0, 1, LENGTH));
+
+        assert_eq!((0..0usize).choose_max_with_stats(&mut rng), None);
+    }
+
+    #[test]
+    fn test_with_stats_matches_without() {
+        // The stats variants make exactly the same choices
+        let mut rng = get_rng();
+        let mut stats_rng = get_rng();
+        for length in 0..LENGTH {
+            let key = |x: &usize| x / 10;
+            assert_eq!(
+                (0..length).choose_max_by_key(&mut rng, key),
+                (0..length).choose_max_by_key_with_stats(&mut stats_rng, key).map(|c| c.item)
+            );
+            assert_eq!(
+                (0..length).map(RoughNumber).choose_min(&mut rng).map(|x| x.0),
+                (0..length).map(RoughNumber).choose_min_with_stats(&mut stats_rng).map(|c| c.item.0)
+            );
+        }
+    }
+
     #[derive(Clone)]
     struct UnhintedIterator<I: Iterator + Clone>(I);
     impl<I: Iterator + Clone> Iterator for UnhintedIterator<I> {
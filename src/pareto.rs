@@ -0,0 +1,153 @@
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+
+use rand::Rng;
+
+use crate::coin_flipper::CoinFlipper;
+
+/// Returns the elements which are not dominated by any other element.
+/// `dominance(a, b)` should return `Some(Greater)` if `a` dominates `b`, `Some(Less)` if `b` dominates `a`
+/// and `Some(Equal)` or `None` if neither dominates the other.
+/// The front is in no particular order.
+pub(crate) fn pareto_front<T, I: Iterator<Item = T>, F: FnMut(&T, &T) -> Option<Ordering>>(
+    iterator: I,
+    mut dominance: F,
+) -> Vec<T> {
+    let mut front: Vec<T> = Vec::new();
+    'items: for item in iterator {
+        let mut i = 0;
+        while i < front.len() {
+            match dominance(&item, &front[i]) {
+                Some(Ordering::Greater) => {
+                    // Order does not matter so this is fine
+                    front.swap_remove(i);
+                }
+                Some(Ordering::Less) => continue 'items,
+                Some(Ordering::Equal) | None => i += 1,
+            }
+        }
+        front.push(item);
+    }
+    front
+}
+
+/// Compares two arrays of keys where larger keys are better.
+/// `a` dominates `b` if every key of `a` is at least as large as the key of `b` and some key is larger.
+pub(crate) fn dominance_by_keys<K: Ord, const N: usize>(a: &[K; N], b: &[K; N]) -> Option<Ordering> {
+    let mut result = Ordering::Equal;
+    for (x, y) in a.iter().zip(b) {
+        match x.cmp(y) {
+            Ordering::Equal => {}
+            ordering if result == Ordering::Equal => result = ordering,
+            ordering if ordering != result => return None,
+            _ => {}
+        }
+    }
+    Some(result)
+}
+
+/// Removes and returns a uniformly random element.
+pub(crate) fn choose_from<T, R: Rng>(mut elements: Vec<T>, rng: &mut R) -> Option<T> {
+    if elements.is_empty() {
+        return None;
+    }
+    let index = CoinFlipper::new(rng).gen_index_below(elements.len());
+    Some(elements.swap_remove(index))
+}
+
+/// Shuffles the elements with the Fisher-Yates shuffle.
+pub(crate) fn shuffle<T, R: Rng>(elements: &mut [T], rng: &mut R) {
+    let mut coin_flipper = CoinFlipper::new(rng);
+    for i in (1..elements.len()).rev() {
+        let j = coin_flipper.gen_index_below(i + 1);
+        elements.swap(i, j);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::cmp::Ordering;
+
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use super::dominance_by_keys;
+    use crate::Kindness;
+
+    const RUNS: usize = 10000;
+
+    /// Points on the front `x + y == 10` and some points behind it
+    fn points() -> impl Iterator<Item = (u32, u32)> {
+        (0..=10).flat_map(|x| (0..=(10 - x)).map(move |y| (x, y)))
+    }
+
+    #[test]
+    fn test_dominance_by_keys() {
+        assert_eq!(dominance_by_keys(&[1, 2], &[1, 2]), Some(Ordering::Equal));
+        assert_eq!(dominance_by_keys(&[2, 2], &[1, 2]), Some(Ordering::Greater));
+        assert_eq!(dominance_by_keys(&[1, 1], &[1, 2]), Some(Ordering::Less));
+        assert_eq!(dominance_by_keys(&[2, 1], &[1, 2]), None);
+        assert_eq!(dominance_by_keys::<u32, 0>(&[], &[]), Some(Ordering::Equal));
+    }
+
+    #[test]
+    fn test_choose_pareto_optimal_by_keys() {
+        let mut counts = [0usize; 11];
+        let mut rng = StdRng::seed_from_u64(123);
+        for _ in 0..RUNS {
+            let (x, y) = points()
+                .choose_pareto_optimal_by_keys(&mut rng, |(x, y)| [*x, *y])
+                .unwrap();
+            assert_eq!(x + y, 10);
+            counts[x as usize] += 1;
+        }
+
+        insta::assert_debug_snapshot!(counts);
+        for x in counts {
+            assert!(x > RUNS / 11 * 8 / 10);
+            assert!(x < RUNS / 11 * 12 / 10);
+        }
+    }
+
+    #[test]
+    fn test_choose_pareto_optimal_by() {
+        let mut rng = StdRng::seed_from_u64(123);
+        let dominance = |a: &(u32, u32), b: &(u32, u32)| dominance_by_keys(&[a.0, a.1], &[b.0, b.1]);
+        for _ in 0..100 {
+            let (x, y) = points().choose_pareto_optimal_by(&mut rng, dominance).unwrap();
+            assert_eq!(x + y, 10);
+        }
+        assert_eq!((0..0).choose_pareto_optimal_by(&mut rng, |a, b| a.partial_cmp(b)), None);
+        // With a total order, the front is the maximum
+        assert_eq!((0..10).choose_pareto_optimal_by(&mut rng, |a, b| a.partial_cmp(b)), Some(9));
+    }
+
+    #[test]
+    fn test_pareto_front_by_keys() {
+        let mut rng = StdRng::seed_from_u64(123);
+        let mut firsts = [0usize; 11];
+        for _ in 0..RUNS {
+            let front = points().pareto_front_by_keys(&mut rng, |(x, y)| [*x, *y]);
+            assert_eq!(front.len(), 11);
+            let mut sorted = front.clone();
+            sorted.sort_unstable();
+            assert_eq!(sorted, (0..=10).map(|x| (x, 10 - x)).collect::<Vec<_>>());
+            firsts[front[0].0 as usize] += 1;
+        }
+
+        // The front is shuffled so any point is equally likely to come first
+        for x in firsts {
+            assert!(x > RUNS / 11 * 8 / 10);
+            assert!(x < RUNS / 11 * 12 / 10);
+        }
+    }
+
+    #[test]
+    fn test_pareto_front_keeps_equal_elements() {
+        let mut rng = StdRng::seed_from_u64(123);
+        let front = [(1, 'a'), (0, 'b'), (1, 'c')]
+            .into_iter()
+            .pareto_front_by_keys(&mut rng, |(x, _)| [*x]);
+        assert_eq!(front.len(), 2);
+        assert!(front.iter().all(|(x, _)| *x == 1));
+    }
+}
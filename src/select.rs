@@ -0,0 +1,160 @@
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+
+use rand::Rng;
+
+use crate::coin_flipper::CoinFlipper;
+
+/// Returns a random element which is equal to the `nth` smallest element (counting from zero) of `elements`.
+/// Every element equal to the `nth` smallest element is equally likely to be chosen.
+/// Returns [`None`] if `nth` is out of bounds.
+///
+/// Uses randomized quickselect with a three-way partition, so takes expected linear time.
+pub(crate) fn choose_nth<T, R: Rng, F: FnMut(&T, &T) -> Ordering>(
+    mut elements: Vec<T>,
+    rng: &mut R,
+    nth: usize,
+    mut compare: F,
+) -> Option<T> {
+    if nth >= elements.len() {
+        return None;
+    }
+    let mut coin_flipper = CoinFlipper::new(rng);
+    let mut lo = 0;
+    let mut hi = elements.len();
+
+    loop {
+        let pivot = lo + coin_flipper.gen_index_below(hi - lo);
+        elements.swap(lo, pivot);
+
+        // Invariant: [lo, lt) < pivot, [lt, i) == pivot, [gt, hi) > pivot
+        // `elements[lt]` is always equal to the pivot
+        let mut lt = lo;
+        let mut i = lo + 1;
+        let mut gt = hi;
+        while i < gt {
+            match compare(&elements[i], &elements[lt]) {
+                Ordering::Less => {
+                    elements.swap(lt, i);
+                    lt += 1;
+                    i += 1;
+                }
+                Ordering::Greater => {
+                    gt -= 1;
+                    elements.swap(i, gt);
+                }
+                Ordering::Equal => i += 1,
+            }
+        }
+
+        if nth < lt {
+            hi = lt;
+        } else if nth >= gt {
+            lo = gt;
+        } else {
+            // Every element equal to the nth smallest element is in [lt, gt)
+            let chosen = lt + coin_flipper.gen_index_below(gt - lt);
+            return Some(elements.swap_remove(chosen));
+        }
+    }
+}
+
+/// The index of the `q` quantile of `len` elements, rounding down.
+/// Panics if `q` is not between zero and one.
+pub(crate) fn quantile_index(len: usize, q: f64) -> usize {
+    assert!((0.0..=1.0).contains(&q), "Quantile {q} is not between 0 and 1");
+    // Casting rounds towards zero, which is down because the product is not negative
+    ((len.saturating_sub(1) as f64) * q) as usize
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use crate::Kindness;
+
+    const RUNS: usize = 10000;
+    const LENGTH: usize = 100;
+    const LOWER_TOLERANCE: usize = 60;
+    const UPPER_TOLERANCE: usize = 140;
+
+    #[test]
+    fn test_choose_nth_smallest() {
+        let mut rng = StdRng::seed_from_u64(123);
+        for length in 1..50usize {
+            for nth in 0..length {
+                // Reverse so that the elements are not already sorted
+                let element = (0..length).rev().choose_nth_smallest(&mut rng, nth);
+                assert_eq!(element, Some(nth));
+            }
+            assert_eq!((0..length).choose_nth_smallest(&mut rng, length), None);
+        }
+    }
+
+    #[test]
+    fn test_choose_nth_smallest_by_key_ties() {
+        let mut counts: [usize; LENGTH] = [0; LENGTH];
+        let mut rng = StdRng::seed_from_u64(123);
+
+        for _ in 0..RUNS {
+            // The 25th smallest has a tens digit of 2
+            let element = (0..LENGTH)
+                .rev()
+                .choose_nth_smallest_by_key(&mut rng, 25, |x| x / 10)
+                .unwrap();
+            counts[element] += 1;
+        }
+
+        insta::assert_debug_snapshot!(counts);
+        for (i, &x) in counts.iter().enumerate() {
+            if i / 10 == 2 {
+                assert!(x > LOWER_TOLERANCE * 10);
+                assert!(x < UPPER_TOLERANCE * 10);
+            } else {
+                assert_eq!(x, 0);
+            }
+        }
+    }
+
+    #[test]
+    fn test_choose_nth_smallest_matches_choose_min() {
+        // The zeroth smallest has the same distribution as the minimum
+        let mut min_counts: [usize; 10] = [0; 10];
+        let mut nth_counts: [usize; 10] = [0; 10];
+        let mut rng = StdRng::seed_from_u64(123);
+
+        for _ in 0..RUNS {
+            let min = (0..LENGTH).choose_min_by_key(&mut rng, |x| x / 10).unwrap();
+            min_counts[min] += 1;
+            let nth = (0..LENGTH).choose_nth_smallest_by_key(&mut rng, 0, |x| x / 10).unwrap();
+            nth_counts[nth] += 1;
+        }
+
+        for (min, nth) in min_counts.into_iter().zip(nth_counts) {
+            // The counts are about 1000 each, so their difference has a standard deviation of about 42
+            assert!(min.abs_diff(nth) < 200, "{min} vs {nth}");
+            assert!(min > LOWER_TOLERANCE * 10);
+            assert!(min < UPPER_TOLERANCE * 10);
+            assert!(nth > LOWER_TOLERANCE * 10);
+            assert!(nth < UPPER_TOLERANCE * 10);
+        }
+    }
+
+    #[test]
+    fn test_choose_quantile_by_key() {
+        let mut rng = StdRng::seed_from_u64(123);
+        assert_eq!((0..5).choose_quantile_by_key(&mut rng, 0.0, |x| *x), Some(0));
+        assert_eq!((0..5).choose_quantile_by_key(&mut rng, 0.5, |x| *x), Some(2));
+        assert_eq!((0..6).choose_quantile_by_key(&mut rng, 0.5, |x| *x), Some(2));
+        assert_eq!((0..5).choose_quantile_by_key(&mut rng, 0.9, |x| *x), Some(3));
+        assert_eq!((0..5).choose_quantile_by_key(&mut rng, 1.0, |x| *x), Some(4));
+        assert_eq!((0..0).choose_quantile_by_key(&mut rng, 0.5, |x| *x), None);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_choose_quantile_by_key_out_of_range() {
+        let mut rng = StdRng::seed_from_u64(123);
+        (0..5).choose_quantile_by_key(&mut rng, 1.5, |x| *x);
+    }
+}
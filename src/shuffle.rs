@@ -0,0 +1,197 @@
+//! Shuffling streams which are too long to collect.
+
+use alloc::vec::Vec;
+use core::fmt;
+use core::iter::FusedIterator;
+use core::num::NonZeroU32;
+
+use rand::RngCore;
+
+use crate::index::gen_index_inclusive;
+use crate::uniform::FastDiceRoller;
+
+/// An iterator which shuffles its elements through a buffer of `capacity` elements.
+///
+/// Once the buffer is full, each element of the inner iterator replaces a uniformly random buffered element,
+/// which is returned. When the inner iterator ends, the rest of the buffer is returned in random order.
+/// If `capacity` is at least the number of elements, the result is a uniformly random permutation.
+///
+/// Created by [`Kindness::shuffle_buffered`](crate::Kindness::shuffle_buffered).
+#[must_use = "iterator adaptors are lazy and do nothing unless consumed"]
+pub struct ShuffleBuffered<I: Iterator, R> {
+    iterator: I,
+    rng: R,
+    roller: FastDiceRoller,
+    buffer: Vec<I::Item>,
+    capacity: usize,
+    /// Whether the inner iterator has returned [`None`]
+    exhausted: bool,
+}
+
+impl<I: Iterator, R> ShuffleBuffered<I, R> {
+    pub(crate) fn new(iterator: I, rng: R, capacity: usize) -> Self {
+        assert!(
+            capacity > 0,
+            "Cannot shuffle with a buffer of zero elements"
+        );
+        Self {
+            iterator,
+            rng,
+            roller: FastDiceRoller::new(NonZeroU32::MIN),
+            buffer: Vec::new(),
+            capacity,
+            exhausted: false,
+        }
+    }
+
+    fn next_inner(&mut self) -> Option<I::Item> {
+        if self.exhausted {
+            return None;
+        }
+        let item = self.iterator.next();
+        self.exhausted = item.is_none();
+        item
+    }
+}
+
+impl<I: Iterator, R: RngCore> Iterator for ShuffleBuffered<I, R> {
+    type Item = I::Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.buffer.len() < self.capacity {
+            match self.next_inner() {
+                Some(item) => self.buffer.push(item),
+                None => break,
+            }
+        }
+        let inclusive_upper_bound = self.buffer.len().checked_sub(1)?;
+        let index = gen_index_inclusive(&mut self.roller, &mut self.rng, inclusive_upper_bound);
+        match self.next_inner() {
+            Some(item) => Some(core::mem::replace(&mut self.buffer[index], item)),
+            None => Some(self.buffer.swap_remove(index)),
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let buffered = self.buffer.len();
+        if self.exhausted {
+            return (buffered, Some(buffered));
+        }
+        let (lower, upper) = self.iterator.size_hint();
+        (
+            lower.saturating_add(buffered),
+            upper.and_then(|upper| upper.checked_add(buffered)),
+        )
+    }
+}
+
+impl<I: Iterator, R: RngCore> FusedIterator for ShuffleBuffered<I, R> {}
+
+impl<I: Iterator + fmt::Debug, R> fmt::Debug for ShuffleBuffered<I, R>
+where
+    I::Item: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ShuffleBuffered")
+            .field("iterator", &self.iterator)
+            .field("buffer", &self.buffer)
+            .field("capacity", &self.capacity)
+            .field("exhausted", &self.exhausted)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use crate::Kindness;
+
+    const RUNS: usize = 10000;
+    const LENGTH: usize = 100;
+    const LOWER_TOLERANCE: usize = 60;
+    const UPPER_TOLERANCE: usize = 140;
+
+    #[test]
+    fn test_shuffle_buffered_is_permutation() {
+        let mut rng = StdRng::seed_from_u64(123);
+        for capacity in [1, 2, 10, LENGTH, 1000] {
+            let mut shuffled: Vec<_> = (0..LENGTH).shuffle_buffered(&mut rng, capacity).collect();
+            shuffled.sort_unstable();
+            assert!(shuffled.into_iter().eq(0..LENGTH));
+        }
+        // A buffer of one element does not shuffle
+        let unshuffled: Vec<_> = (0..LENGTH).shuffle_buffered(&mut rng, 1).collect();
+        assert!(unshuffled.into_iter().eq(0..LENGTH));
+    }
+
+    #[test]
+    fn test_shuffle_buffered_full_buffer_is_uniform() {
+        // With a buffer as large as the iterator, the first element is uniformly random
+        let mut counts: [usize; LENGTH] = [0; LENGTH];
+        let mut rng = StdRng::seed_from_u64(123);
+
+        for _ in 0..RUNS {
+            let first = (0..LENGTH)
+                .filter(|_| true)
+                .shuffle_buffered(&mut rng, LENGTH)
+                .next()
+                .unwrap();
+            counts[first] += 1;
+        }
+
+        insta::assert_debug_snapshot!(counts);
+        for x in counts {
+            assert!(x > LOWER_TOLERANCE);
+            assert!(x < UPPER_TOLERANCE);
+        }
+    }
+
+    #[test]
+    fn test_shuffle_buffered_displacement() {
+        // Only buffered elements can be returned, so no element moves more than `capacity - 1` places earlier
+        let mut rng = StdRng::seed_from_u64(123);
+        let capacity = 10;
+        for (position, x) in (0..LENGTH).shuffle_buffered(&mut rng, capacity).enumerate() {
+            assert!(x <= position + capacity - 1);
+        }
+    }
+
+    #[test]
+    fn test_shuffle_buffered_is_lazy() {
+        let mut rng = StdRng::seed_from_u64(123);
+        let mut consumed = 0;
+        let first = (0..)
+            .inspect(|_| consumed += 1)
+            .shuffle_buffered(&mut rng, 10)
+            .next()
+            .unwrap();
+        assert!(first < 10);
+        // The buffer and the element which replaces the one returned
+        assert_eq!(consumed, 11);
+    }
+
+    #[test]
+    fn test_shuffle_buffered_size_hint() {
+        let mut rng = StdRng::seed_from_u64(123);
+        let mut shuffled = (0..LENGTH).shuffle_buffered(&mut rng, 10);
+        for remaining in (0..=LENGTH).rev() {
+            assert_eq!(shuffled.size_hint(), (remaining, Some(remaining)));
+            shuffled.next();
+        }
+        assert_eq!(shuffled.next(), None);
+
+        let shuffled = (0..LENGTH).filter(|_| true).shuffle_buffered(&mut rng, 10);
+        assert_eq!(shuffled.size_hint(), (0, Some(LENGTH)));
+        let mut shuffled = (0..3).shuffle_buffered(&mut rng, 10);
+        shuffled.next();
+        assert_eq!(shuffled.size_hint(), (2, Some(2)));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_shuffle_buffered_zero_capacity() {
+        let mut rng = StdRng::seed_from_u64(123);
+        let _ = (0..LENGTH).shuffle_buffered(&mut rng, 0);
+    }
+}
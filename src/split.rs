@@ -0,0 +1,557 @@
+//! Randomly splitting elements into parts, e.g. for training and test sets or for cross-validation.
+
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+use core::fmt;
+use core::num::NonZeroU32;
+
+use rand::{Rng, RngCore};
+
+use crate::coin_flipper::CoinFlipper;
+use crate::index::gen_index_inclusive;
+use crate::uniform::FastDiceRoller;
+
+/// `2^64`, the denominator used to turn a ratio into an exact probability
+const TWO_TO_64: f64 = 18_446_744_073_709_551_616.0;
+
+fn assert_ratio(ratio: f64) {
+    assert!(
+        (0.0..=1.0).contains(&ratio),
+        "Ratio {ratio} is not between 0 and 1"
+    );
+}
+
+/// Puts each element in the first part independently with probability `ratio`, rounded to a multiple of `2^-64`.
+pub(crate) fn random_split<I: Iterator, R: Rng>(
+    iterator: I,
+    rng: &mut R,
+    ratio: f64,
+) -> (Vec<I::Item>, Vec<I::Item>) {
+    assert_ratio(ratio);
+    // Casting rounds towards zero
+    let numerator = (ratio * TWO_TO_64) as u128;
+    let denominator = 1_u128 << 64;
+    let mut coin_flipper = CoinFlipper::new(rng);
+    let mut first = Vec::new();
+    let mut second = Vec::new();
+    for item in iterator {
+        if numerator > 0 && coin_flipper.gen_ratio(numerator, denominator) {
+            first.push(item);
+        } else {
+            second.push(item);
+        }
+    }
+    (first, second)
+}
+
+/// Puts exactly `len * ratio` elements, rounded down, in the first part.
+/// Every subset of that size is equally likely.
+pub(crate) fn random_split_exact<I: Iterator, R: Rng>(
+    iterator: I,
+    rng: &mut R,
+    ratio: f64,
+) -> (Vec<I::Item>, Vec<I::Item>) {
+    assert_ratio(ratio);
+    let (lower, upper) = iterator.size_hint();
+    if upper == Some(lower) {
+        split_exact(iterator, lower, rng, ratio)
+    } else {
+        let elements: Vec<I::Item> = iterator.collect();
+        let len = elements.len();
+        split_exact(elements.into_iter(), len, rng, ratio)
+    }
+}
+
+fn split_exact<I: Iterator, R: Rng>(
+    iterator: I,
+    len: usize,
+    rng: &mut R,
+    ratio: f64,
+) -> (Vec<I::Item>, Vec<I::Item>) {
+    let needed = exact_len(len, ratio);
+    let mut first = Vec::with_capacity(needed);
+    let mut second = Vec::with_capacity(len - needed);
+    let mut roller = FastDiceRoller::new(NonZeroU32::MIN);
+    let mut selection = Selection::new(needed, len);
+    for item in iterator {
+        if selection.choose(&mut roller, rng) {
+            first.push(item);
+        } else {
+            second.push(item);
+        }
+    }
+    (first, second)
+}
+
+/// `len * ratio` rounded down.
+/// The product can round above `len` in floating point when `len` is large, so the result is capped at `len`.
+fn exact_len(len: usize, ratio: f64) -> usize {
+    // Casting rounds towards zero, which is down because the product is not negative
+    (((len as f64) * ratio) as usize).min(len)
+}
+
+/// Selection sampling of `needed` out of `remaining` elements, one element at a time.
+#[derive(Debug, Clone)]
+struct Selection {
+    needed: usize,
+    remaining: usize,
+}
+
+impl Selection {
+    fn new(needed: usize, remaining: usize) -> Self {
+        Self { needed, remaining }
+    }
+
+    /// Chooses the next element with probability `needed / remaining`.
+    fn choose<R: RngCore>(&mut self, roller: &mut FastDiceRoller, rng: &mut R) -> bool {
+        let chosen = self.needed > 0
+            && (self.needed >= self.remaining
+                || gen_index_inclusive(roller, rng, self.remaining - 1) < self.needed);
+        self.remaining = self.remaining.saturating_sub(1);
+        if chosen {
+            self.needed -= 1;
+        }
+        chosen
+    }
+}
+
+/// Collects the elements, calling `key` once for each, and numbers the strata in order of first appearance.
+/// Returns the elements with their stratum numbers, and the size of each stratum.
+fn collect_strata<I: Iterator, K: Ord, F: FnMut(&I::Item) -> K>(
+    iterator: I,
+    mut key: F,
+) -> (Vec<(usize, I::Item)>, Vec<usize>) {
+    let mut ids = BTreeMap::new();
+    let mut sizes = Vec::new();
+    let elements = iterator
+        .map(|item| {
+            let id = *ids.entry(key(&item)).or_insert_with(|| {
+                sizes.push(0);
+                sizes.len() - 1
+            });
+            sizes[id] += 1;
+            (id, item)
+        })
+        .collect();
+    (elements, sizes)
+}
+
+fn split_strata<T, R: Rng>(
+    elements: Vec<(usize, T)>,
+    mut selections: Vec<Selection>,
+    rng: &mut R,
+) -> (Vec<T>, Vec<T>) {
+    let mut roller = FastDiceRoller::new(NonZeroU32::MIN);
+    let needed: usize = selections.iter().map(|s| s.needed).sum();
+    let mut first = Vec::with_capacity(needed);
+    let mut second = Vec::with_capacity(elements.len() - needed);
+    for (id, item) in elements {
+        if selections[id].choose(&mut roller, rng) {
+            first.push(item);
+        } else {
+            second.push(item);
+        }
+    }
+    (first, second)
+}
+
+/// Splits each stratum so that its first part has `len * ratio` elements,
+/// rounded up with probability equal to the fractional part and otherwise down,
+/// so each element is in the first part with probability `ratio`, rounded to a multiple of `2^-64`.
+pub(crate) fn random_split_by_key<I: Iterator, R: Rng, K: Ord, F: FnMut(&I::Item) -> K>(
+    iterator: I,
+    rng: &mut R,
+    ratio: f64,
+    key: F,
+) -> (Vec<I::Item>, Vec<I::Item>) {
+    assert_ratio(ratio);
+    let (elements, sizes) = collect_strata(iterator, key);
+    let denominator = 1_u128 << 64;
+    let mut coin_flipper = CoinFlipper::new(&mut *rng);
+    let selections = sizes
+        .into_iter()
+        .map(|len| {
+            let needed = exact_len(len, ratio);
+            // The fractional part, which is only meaningful if `needed` was not capped at `len`
+            let numerator = (((len as f64) * ratio - needed as f64) * TWO_TO_64) as u128;
+            let round_up =
+                needed < len && numerator > 0 && coin_flipper.gen_ratio(numerator, denominator);
+            Selection::new(needed + usize::from(round_up), len)
+        })
+        .collect();
+    split_strata(elements, selections, rng)
+}
+
+/// Puts exactly `len * ratio` elements, rounded down, of each stratum in the first part.
+/// Every such subset is equally likely.
+pub(crate) fn random_split_exact_by_key<I: Iterator, R: Rng, K: Ord, F: FnMut(&I::Item) -> K>(
+    iterator: I,
+    rng: &mut R,
+    ratio: f64,
+    key: F,
+) -> (Vec<I::Item>, Vec<I::Item>) {
+    assert_ratio(ratio);
+    let (elements, sizes) = collect_strata(iterator, key);
+    let selections = sizes
+        .into_iter()
+        .map(|len| Selection::new(exact_len(len, ratio), len))
+        .collect();
+    split_strata(elements, selections, rng)
+}
+
+/// The folds which have not been used yet in the current block of `k` elements.
+#[derive(Debug, Clone)]
+struct FoldDeck {
+    /// A permutation of `0..k` whose first `remaining` elements have not been used
+    folds: Vec<usize>,
+    remaining: usize,
+}
+
+impl FoldDeck {
+    fn new(k: usize) -> Self {
+        assert!(k > 0, "Cannot assign elements to zero folds");
+        Self {
+            folds: (0..k).collect(),
+            remaining: k,
+        }
+    }
+
+    fn draw<R: RngCore>(&mut self, roller: &mut FastDiceRoller, rng: &mut R) -> usize {
+        if self.remaining == 0 {
+            self.remaining = self.folds.len();
+        }
+        let index = gen_index_inclusive(roller, rng, self.remaining - 1);
+        self.remaining -= 1;
+        self.folds.swap(index, self.remaining);
+        self.folds[self.remaining]
+    }
+}
+
+/// An iterator which tags each element with a random fold id in `0..k`.
+///
+/// Each consecutive block of `k` elements gets every fold id exactly once, in a random order,
+/// so the sizes of the folds never differ by more than one.
+///
+/// Created by [`Kindness::k_fold_assign`](crate::Kindness::k_fold_assign).
+#[must_use = "iterator adaptors are lazy and do nothing unless consumed"]
+pub struct KFoldAssign<I, R> {
+    iterator: I,
+    rng: R,
+    roller: FastDiceRoller,
+    deck: FoldDeck,
+}
+
+impl<I, R> KFoldAssign<I, R> {
+    pub(crate) fn new(iterator: I, rng: R, k: usize) -> Self {
+        Self {
+            iterator,
+            rng,
+            roller: FastDiceRoller::new(NonZeroU32::MIN),
+            deck: FoldDeck::new(k),
+        }
+    }
+}
+
+impl<I: Iterator, R: RngCore> Iterator for KFoldAssign<I, R> {
+    type Item = (usize, I::Item);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let item = self.iterator.next()?;
+        Some((self.deck.draw(&mut self.roller, &mut self.rng), item))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iterator.size_hint()
+    }
+}
+
+impl<I: fmt::Debug, R> fmt::Debug for KFoldAssign<I, R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("KFoldAssign")
+            .field("iterator", &self.iterator)
+            .field("deck", &self.deck)
+            .finish_non_exhaustive()
+    }
+}
+
+/// An iterator which tags each element with a random fold id in `0..k`, balancing the folds within each stratum.
+///
+/// Elements are grouped into strata by their key.
+/// Within each stratum, each consecutive block of `k` elements gets every fold id exactly once, in a random order.
+///
+/// Created by [`Kindness::k_fold_assign_by_key`](crate::Kindness::k_fold_assign_by_key).
+#[must_use = "iterator adaptors are lazy and do nothing unless consumed"]
+pub struct KFoldAssignByKey<I, R, F, K> {
+    iterator: I,
+    rng: R,
+    key: F,
+    roller: FastDiceRoller,
+    k: usize,
+    decks: BTreeMap<K, FoldDeck>,
+}
+
+impl<I, R, F, K> KFoldAssignByKey<I, R, F, K> {
+    pub(crate) fn new(iterator: I, rng: R, k: usize, key: F) -> Self {
+        assert!(k > 0, "Cannot assign elements to zero folds");
+        Self {
+            iterator,
+            rng,
+            key,
+            roller: FastDiceRoller::new(NonZeroU32::MIN),
+            k,
+            decks: BTreeMap::new(),
+        }
+    }
+}
+
+impl<I: Iterator, R: RngCore, K: Ord, F: FnMut(&I::Item) -> K> Iterator
+    for KFoldAssignByKey<I, R, F, K>
+{
+    type Item = (usize, I::Item);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let item = self.iterator.next()?;
+        let k = self.k;
+        let deck = self
+            .decks
+            .entry((self.key)(&item))
+            .or_insert_with(|| FoldDeck::new(k));
+        Some((deck.draw(&mut self.roller, &mut self.rng), item))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iterator.size_hint()
+    }
+}
+
+impl<I: fmt::Debug, R, F, K: fmt::Debug> fmt::Debug for KFoldAssignByKey<I, R, F, K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("KFoldAssignByKey")
+            .field("iterator", &self.iterator)
+            .field("k", &self.k)
+            .field("decks", &self.decks)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use crate::Kindness;
+
+    const RUNS: usize = 10000;
+    const LENGTH: usize = 100;
+    const LOWER_TOLERANCE: usize = 60;
+    const UPPER_TOLERANCE: usize = 140;
+
+    fn assert_is_split(first: &[usize], second: &[usize]) {
+        assert!(first.windows(2).all(|w| w[0] < w[1]));
+        assert!(second.windows(2).all(|w| w[0] < w[1]));
+        let mut all: Vec<_> = first.iter().chain(second).copied().collect();
+        all.sort_unstable();
+        assert!(all.into_iter().eq(0..LENGTH));
+    }
+
+    #[test]
+    fn test_random_split() {
+        let mut counts: [usize; LENGTH] = [0; LENGTH];
+        let mut rng = StdRng::seed_from_u64(123);
+        let mut total = 0;
+
+        for _ in 0..RUNS {
+            let (first, second) = (0..LENGTH).random_split(&mut rng, 0.1);
+            assert_is_split(&first, &second);
+            total += first.len();
+            for x in first {
+                counts[x] += 1;
+            }
+        }
+
+        insta::assert_debug_snapshot!(counts);
+        assert!(total > RUNS * 10 * 95 / 100);
+        assert!(total < RUNS * 10 * 105 / 100);
+        for x in counts {
+            assert!(x > LOWER_TOLERANCE * 10);
+            assert!(x < UPPER_TOLERANCE * 10);
+        }
+    }
+
+    #[test]
+    fn test_random_split_extremes() {
+        let mut rng = StdRng::seed_from_u64(123);
+        let (first, second) = (0..LENGTH).random_split(&mut rng, 0.0);
+        assert!(first.is_empty());
+        assert_eq!(second.len(), LENGTH);
+        let (first, second) = (0..LENGTH).random_split(&mut rng, 1.0);
+        assert_eq!(first.len(), LENGTH);
+        assert!(second.is_empty());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_random_split_out_of_range() {
+        let mut rng = StdRng::seed_from_u64(123);
+        (0..LENGTH).random_split(&mut rng, 1.5);
+    }
+
+    #[test]
+    fn test_random_split_exact() {
+        let mut counts: [usize; LENGTH] = [0; LENGTH];
+        let mut rng = StdRng::seed_from_u64(123);
+
+        for _ in 0..RUNS {
+            let (first, second) = (0..LENGTH).random_split_exact(&mut rng, 0.1);
+            assert_is_split(&first, &second);
+            assert_eq!(first.len(), 10);
+            for x in first {
+                counts[x] += 1;
+            }
+        }
+
+        insta::assert_debug_snapshot!(counts);
+        for x in counts {
+            assert!(x > LOWER_TOLERANCE * 10);
+            assert!(x < UPPER_TOLERANCE * 10);
+        }
+    }
+
+    #[test]
+    fn test_random_split_exact_unhinted() {
+        let mut rng = StdRng::seed_from_u64(123);
+        for ratio in [0.0, 0.25, 0.333, 0.5, 0.999, 1.0] {
+            let (first, second) = (0..LENGTH)
+                .filter(|_| true)
+                .random_split_exact(&mut rng, ratio);
+            assert_is_split(&first, &second);
+            assert_eq!(first.len(), ((LENGTH as f64) * ratio) as usize);
+        }
+    }
+
+    #[test]
+    fn test_exact_len_large() {
+        // On 64-bit targets this is 2^54 - 1, which rounds up to 2^54 as a float
+        let len = usize::MAX >> 10;
+        assert_eq!(super::exact_len(len, 1.0), len);
+        assert_eq!(super::exact_len(usize::MAX, 1.0), usize::MAX);
+
+        let mut rng = StdRng::seed_from_u64(123);
+        let len = 1 << 20;
+        let (first, second) = (0..len).random_split_exact(&mut rng, 1.0);
+        assert_eq!(first.len(), len);
+        assert!(second.is_empty());
+        let (first, second) = (0..len).random_split_by_key(&mut rng, 1.0, |x| x % 3);
+        assert_eq!(first.len(), len);
+        assert!(second.is_empty());
+    }
+
+    #[test]
+    fn test_random_split_by_key() {
+        let mut counts: [usize; LENGTH] = [0; LENGTH];
+        let mut rng = StdRng::seed_from_u64(123);
+
+        for _ in 0..RUNS {
+            let (first, second) = (0..LENGTH).random_split_by_key(&mut rng, 0.1, |x| x % 3);
+            assert_is_split(&first, &second);
+            // The strata have 34, 33 and 33 elements
+            for (stratum, len) in [34, 33, 33].into_iter().enumerate() {
+                let chosen = first.iter().filter(|x| *x % 3 == stratum).count();
+                assert!(chosen == len / 10 || chosen == len / 10 + 1);
+            }
+            for x in first {
+                counts[x] += 1;
+            }
+        }
+
+        insta::assert_debug_snapshot!(counts);
+        for x in counts {
+            assert!(x > LOWER_TOLERANCE * 10);
+            assert!(x < UPPER_TOLERANCE * 10);
+        }
+    }
+
+    #[test]
+    fn test_random_split_exact_by_key() {
+        let mut counts: [usize; LENGTH] = [0; LENGTH];
+        let mut rng = StdRng::seed_from_u64(123);
+
+        for _ in 0..RUNS {
+            let (first, second) = (0..LENGTH).random_split_exact_by_key(&mut rng, 0.1, |x| x % 4 == 0);
+            assert_is_split(&first, &second);
+            // 25 elements are multiples of 4 and 75 are not
+            assert_eq!(first.iter().filter(|x| *x % 4 == 0).count(), 2);
+            assert_eq!(first.len(), 9);
+            for x in first {
+                counts[x] += 1;
+            }
+        }
+
+        insta::assert_debug_snapshot!(counts);
+        for (i, x) in counts.into_iter().enumerate() {
+            let expected = if i % 4 == 0 { RUNS * 2 / 25 } else { RUNS * 7 / 75 };
+            assert!(x > expected * 9 / 10);
+            assert!(x < expected * 11 / 10);
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_random_split_by_key_out_of_range() {
+        let mut rng = StdRng::seed_from_u64(123);
+        (0..LENGTH).random_split_by_key(&mut rng, -0.5, |x| x % 2);
+    }
+
+    #[test]
+    fn test_k_fold_assign() {
+        let mut counts = [[0usize; 3]; LENGTH];
+        let mut rng = StdRng::seed_from_u64(123);
+
+        for _ in 0..RUNS {
+            let mut sizes = [0usize; 3];
+            for (i, (fold, x)) in (0..LENGTH).k_fold_assign(&mut rng, 3).enumerate() {
+                assert_eq!(i, x);
+                sizes[fold] += 1;
+                counts[x][fold] += 1;
+                // Balanced at every point
+                assert!(sizes.iter().max().unwrap() - sizes.iter().min().unwrap() <= 1);
+            }
+        }
+
+        for folds in counts {
+            for x in folds {
+                assert!(x > RUNS / 3 * 9 / 10);
+                assert!(x < RUNS / 3 * 11 / 10);
+            }
+        }
+    }
+
+    #[test]
+    fn test_k_fold_assign_by_key() {
+        let mut counts = [[0usize; 4]; LENGTH];
+        let mut rng = StdRng::seed_from_u64(123);
+
+        for _ in 0..RUNS {
+            let mut sizes = [[0usize; 4]; 3];
+            for (fold, x) in (0..LENGTH).k_fold_assign_by_key(&mut rng, 4, |x| x % 3) {
+                let stratum = &mut sizes[x % 3];
+                stratum[fold] += 1;
+                counts[x][fold] += 1;
+                assert!(stratum.iter().max().unwrap() - stratum.iter().min().unwrap() <= 1);
+            }
+        }
+
+        for folds in counts {
+            for x in folds {
+                assert!(x > RUNS / 4 * 9 / 10);
+                assert!(x < RUNS / 4 * 11 / 10);
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_k_fold_assign_zero_folds() {
+        let mut rng = StdRng::seed_from_u64(123);
+        let _ = (0..LENGTH).k_fold_assign(&mut rng, 0);
+    }
+}
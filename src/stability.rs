@@ -0,0 +1,306 @@
+//! Golden value tests for the value stability contract (see the crate docs).
+//!
+//! Every test here must give the same results on 32-bit and 64-bit targets.
+//! If one of these snapshots changes, the output of a public method has changed for the same seed,
+//! which must be noted in the changelog.
+
+use core::num::NonZeroU32;
+
+use rand::{rngs::StdRng, SeedableRng};
+
+use crate::{uniform::Uniform, Counter, Kindness};
+
+const SEEDS: u64 = 32;
+const LENGTH: usize = 100;
+
+fn for_each_seed<T>(mut f: impl FnMut(&mut StdRng) -> T) -> Vec<T> {
+    (0..SEEDS)
+        .map(|seed| f(&mut StdRng::seed_from_u64(seed)))
+        .collect()
+}
+
+/// Run `f` counting with `usize` and with `u32`, which is what `usize` is on 32-bit targets.
+fn with_both_counters<T: PartialEq + core::fmt::Debug>(
+    f: impl Fn(&mut StdRng, bool) -> T,
+) -> Vec<T> {
+    let narrow = for_each_seed(|rng| f(rng, true));
+    let native = for_each_seed(|rng| f(rng, false));
+    assert_eq!(narrow, native);
+    native
+}
+
+fn choose_item<C: Counter>(iterator: impl Iterator<Item = usize>, rng: &mut StdRng) -> Option<usize> {
+    iterator.wide_counter::<C>().choose_item(rng)
+}
+
+#[test]
+fn golden_choose_item_exact_size() {
+    let results = with_both_counters(|rng, narrow| {
+        if narrow {
+            choose_item::<u32>(0..LENGTH, rng)
+        } else {
+            (0..LENGTH).choose_item(rng)
+        }
+    });
+    insta::assert_debug_snapshot!(results);
+}
+
+#[test]
+fn golden_choose_item_windowed() {
+    let results = with_both_counters(|rng, narrow| {
+        let iterator = WindowHinted(0..LENGTH, 7);
+        if narrow {
+            choose_item::<u32>(iterator, rng)
+        } else {
+            iterator.choose_item(rng)
+        }
+    });
+    insta::assert_debug_snapshot!(results);
+}
+
+#[test]
+fn golden_choose_item_unhinted() {
+    let results = with_both_counters(|rng, narrow| {
+        let iterator = (0..LENGTH).filter(|_| true);
+        if narrow {
+            choose_item::<u32>(iterator, rng)
+        } else {
+            iterator.choose_item(rng)
+        }
+    });
+    insta::assert_debug_snapshot!(results);
+}
+
+#[test]
+fn golden_choose_max_and_min() {
+    let results = with_both_counters(|rng, narrow| {
+        let key = |x: &usize| x / 10;
+        let compare = |a: &usize, b: &usize| key(a).cmp(&key(b));
+        if narrow {
+            [
+                (0..LENGTH).wide_counter::<u32>().choose_max_by_key(rng, key),
+                (0..LENGTH).wide_counter::<u32>().choose_max_by(rng, compare),
+                (0..LENGTH).wide_counter::<u32>().choose_min_by_key(rng, key),
+                (0..LENGTH).wide_counter::<u32>().choose_min_by(rng, compare),
+            ]
+        } else {
+            [
+                (0..LENGTH).choose_max_by_key(rng, key),
+                (0..LENGTH).choose_max_by(rng, compare),
+                (0..LENGTH).choose_min_by_key(rng, key),
+                (0..LENGTH).choose_min_by(rng, compare),
+            ]
+        }
+    });
+    insta::assert_debug_snapshot!(results);
+}
+
+#[test]
+fn golden_choose_unique() {
+    // The order of the results depends on the hasher, but which duplicates are chosen does not
+    let results = with_both_counters(|rng, narrow| {
+        let iterator = (0..LENGTH).map(|x| Tens(x));
+        let mut unique: Vec<usize> = if narrow {
+            iterator.wide_counter::<u32>().choose_unique(rng).map(|x| x.0).collect()
+        } else {
+            iterator.choose_unique(rng).map(|x| x.0).collect()
+        };
+        unique.sort_unstable();
+        unique
+    });
+    insta::assert_debug_snapshot!(results);
+}
+
+#[test]
+fn golden_choose_unique_by_key() {
+    let results = with_both_counters(|rng, narrow| {
+        let mut unique: Vec<usize> = if narrow {
+            (0..LENGTH)
+                .wide_counter::<u32>()
+                .choose_unique_by_key(rng, |x| x / 10)
+                .collect()
+        } else {
+            (0..LENGTH).choose_unique_by_key(rng, |x| x / 10).collect()
+        };
+        unique.sort_unstable();
+        unique
+    });
+    insta::assert_debug_snapshot!(results);
+}
+
+#[test]
+fn golden_choose_multiple() {
+    let results = for_each_seed(|rng| {
+        [
+            (0..LENGTH).choose_multiple(rng, 5),
+            WindowHinted(0..LENGTH, 7).choose_multiple(rng, 5),
+        ]
+    });
+    insta::assert_debug_snapshot!(results);
+}
+
+#[test]
+fn golden_choose_multiple_ordered() {
+    let results = for_each_seed(|rng| {
+        [
+            (0..LENGTH).choose_multiple_ordered(rng, 5),
+            WindowHinted(0..LENGTH, 7).choose_multiple_ordered(rng, 5),
+        ]
+    });
+    insta::assert_debug_snapshot!(results);
+}
+
+#[test]
+fn golden_random_split() {
+    let results = for_each_seed(|rng| {
+        let (first, _) = (0..20).random_split(rng, 0.3);
+        let (exact, _) = (0..20).random_split_exact(rng, 0.3);
+        let folds: Vec<_> = (0..12).k_fold_assign(&mut *rng, 4).map(|(fold, _)| fold).collect();
+        let stratified: Vec<_> = (0..12)
+            .k_fold_assign_by_key(&mut *rng, 3, |x| x % 2)
+            .map(|(fold, _)| fold)
+            .collect();
+        [first, exact, folds, stratified]
+    });
+    insta::assert_debug_snapshot!(results);
+}
+
+#[test]
+fn golden_random_split_by_key() {
+    let results = for_each_seed(|rng| {
+        let (first, _) = (0..20).random_split_by_key(rng, 0.3, |x| x % 3);
+        let (exact, _) = (0..20).random_split_exact_by_key(rng, 0.3, |x| x % 3);
+        [first, exact]
+    });
+    insta::assert_debug_snapshot!(results);
+}
+
+#[test]
+fn golden_interleave_random() {
+    let results = for_each_seed(|rng| {
+        let hinted: Vec<_> = crate::interleave_random(&mut *rng, [0..3, 10..14, 20..22]).collect();
+        let unhinted: Vec<_> =
+            crate::interleave_random(&mut *rng, [0..3, 10..14].map(|x| x.filter(|_| true))).collect();
+        [hinted, unhinted]
+    });
+    insta::assert_debug_snapshot!(results);
+}
+
+#[test]
+fn golden_bootstrap() {
+    let results = for_each_seed(|rng| {
+        let mut bootstrap = (0..10).bootstrap(&mut *rng, 2);
+        let counts = bootstrap.next_replicate().unwrap().counts().to_vec();
+        let indices: Vec<_> = bootstrap.next_replicate().unwrap().indices().collect();
+        [counts, indices]
+    });
+    insta::assert_debug_snapshot!(results);
+}
+
+#[test]
+fn golden_shuffle_buffered() {
+    let results = for_each_seed(|rng| {
+        let hinted: Vec<_> = (0..12).shuffle_buffered(&mut *rng, 4).collect();
+        let unhinted: Vec<_> = (0..12).filter(|_| true).shuffle_buffered(&mut *rng, 4).collect();
+        [hinted, unhinted]
+    });
+    insta::assert_debug_snapshot!(results);
+}
+
+#[test]
+fn golden_sample_distinct() {
+    let results = for_each_seed(|rng| {
+        [
+            crate::index::sample_distinct(rng, LENGTH, 5),
+            crate::index::sample_distinct_sorted(rng, LENGTH, 5),
+            crate::index::sample_distinct_dense(rng, LENGTH, 5),
+        ]
+    });
+    insta::assert_debug_snapshot!(results);
+}
+
+#[test]
+fn golden_choose_nth_smallest() {
+    let results = for_each_seed(|rng| {
+        [
+            (0..LENGTH).choose_nth_smallest_by_key(rng, 25, |x| x / 10),
+            (0..LENGTH).rev().choose_quantile_by_key(rng, 0.5, |x| x / 10),
+        ]
+    });
+    insta::assert_debug_snapshot!(results);
+}
+
+#[test]
+fn golden_pareto() {
+    let results = for_each_seed(|rng| {
+        let keys = |x: &usize| [x / 10, x % 10];
+        let candidates = (0..LENGTH).filter(|x| x / 10 + x % 10 <= 9);
+        (
+            candidates.clone().choose_pareto_optimal_by_keys(rng, keys),
+            candidates.pareto_front_by_keys(rng, keys),
+        )
+    });
+    insta::assert_debug_snapshot!(results);
+}
+
+#[test]
+fn golden_choose_dedup() {
+    let results = for_each_seed(|rng| {
+        (0..LENGTH)
+            .choose_dedup_by_key(rng, |x| x / 10)
+            .collect::<Vec<_>>()
+    });
+    insta::assert_debug_snapshot!(results);
+}
+
+#[test]
+fn golden_alias_table() {
+    let table = (0..10usize).collect_alias_table(|x| *x as u64 + 1).unwrap();
+    let results = for_each_seed(|rng| table.sample_iter(rng).take(10).copied().collect::<Vec<_>>());
+    insta::assert_debug_snapshot!(results);
+}
+
+#[test]
+fn golden_uniform() {
+    let results = for_each_seed(|rng| {
+        let n = NonZeroU32::new(10).unwrap();
+        let mut packed = Uniform::new(n);
+        let mut fast_dice_roller = Uniform::new_fast_dice_roller(n);
+        let packed: Vec<u32> = (0..10).map(|_| packed.next(rng)).collect();
+        let fast_dice_roller: Vec<u32> = (0..10).map(|_| fast_dice_roller.next(rng)).collect();
+        (packed, fast_dice_roller)
+    });
+    insta::assert_debug_snapshot!(results);
+}
+
+struct WindowHinted<I: ExactSizeIterator>(I, usize);
+
+impl<I: ExactSizeIterator> Iterator for WindowHinted<I> {
+    type Item = I::Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (core::cmp::min(self.0.len(), self.1), None)
+    }
+}
+
+/// A number which is only compared and hashed by its tens digit
+#[derive(Debug, Clone, Copy)]
+struct Tens(usize);
+
+impl PartialEq for Tens {
+    fn eq(&self, other: &Self) -> bool {
+        self.0 / 10 == other.0 / 10
+    }
+}
+
+impl Eq for Tens {}
+
+impl core::hash::Hash for Tens {
+    fn hash<H: core::hash::Hasher>(&self, state: &mut H) {
+        (self.0 / 10).hash(state);
+    }
+}
@@ -0,0 +1,285 @@
+//! Support for fallible random number generators such as `OsRng`.
+//!
+//! Every method in this crate takes an infallible [`rand::Rng`].
+//! To use a [`TryRngCore`] instead, call the method inside [`try_with_rng`], which returns the first error of the generator.
+//!
+//! ```
+//! use kindness::*;
+//! use rand::SeedableRng;
+//!
+//! // Any infallible generator is also a `TryRngCore`
+//! let mut rng = rand::rngs::StdRng::seed_from_u64(123);
+//! let m = try_rng::try_with_rng(&mut rng, |rng| [3, 2, 1, 2, 3].iter().choose_max(rng));
+//! assert_eq!(m, Ok(Some(&3)));
+//! ```
+
+use rand::{RngCore, TryRngCore};
+
+/// An [`RngCore`] which wraps a [`TryRngCore`] and remembers its first error.
+///
+/// After an error, the wrapped generator is not called again and values come from a fixed SplitMix64 sequence instead.
+/// A constant stream such as all zeros would make rejection sampling loops run forever,
+/// but on this sequence every method in this crate finishes as it would with any other generator.
+/// Its result is meaningless, so check [`ErrorCapturingRng::into_result`] before using it.
+///
+/// Usually created by [`try_with_rng`].
+#[derive(Debug)]
+pub struct ErrorCapturingRng<'a, R: TryRngCore + ?Sized> {
+    rng: &'a mut R,
+    error: Option<R::Error>,
+    /// The state of the SplitMix64 sequence used after an error
+    fallback: u64,
+}
+
+impl<'a, R: TryRngCore + ?Sized> ErrorCapturingRng<'a, R> {
+    /// Wraps a fallible generator.
+    pub fn new(rng: &'a mut R) -> Self {
+        Self {
+            rng,
+            error: None,
+            fallback: 0,
+        }
+    }
+
+    /// The first error of the generator, if there has been one.
+    #[must_use]
+    pub const fn error(&self) -> Option<&R::Error> {
+        self.error.as_ref()
+    }
+
+    /// Returns `value` if the generator has not failed, otherwise its first error.
+    pub fn into_result<X>(self, value: X) -> Result<X, R::Error> {
+        match self.error {
+            Some(error) => Err(error),
+            None => Ok(value),
+        }
+    }
+
+    #[inline]
+    fn capture<X>(&mut self, f: impl FnOnce(&mut R) -> Result<X, R::Error>) -> Option<X> {
+        if self.error.is_some() {
+            return None;
+        }
+        match f(self.rng) {
+            Ok(value) => Some(value),
+            Err(error) => {
+                self.error = Some(error);
+                None
+            }
+        }
+    }
+
+    /// The next value of the SplitMix64 sequence.
+    fn next_fallback(&mut self) -> u64 {
+        self.fallback = self.fallback.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.fallback;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+}
+
+impl<R: TryRngCore + ?Sized> RngCore for ErrorCapturingRng<'_, R> {
+    #[inline]
+    fn next_u32(&mut self) -> u32 {
+        match self.capture(TryRngCore::try_next_u32) {
+            Some(value) => value,
+            None => (self.next_fallback() >> 32) as u32,
+        }
+    }
+
+    #[inline]
+    fn next_u64(&mut self) -> u64 {
+        match self.capture(TryRngCore::try_next_u64) {
+            Some(value) => value,
+            None => self.next_fallback(),
+        }
+    }
+
+    fn fill_bytes(&mut self, dst: &mut [u8]) {
+        if self.capture(|rng| rng.try_fill_bytes(dst)).is_none() {
+            for chunk in dst.chunks_mut(8) {
+                let bytes = self.next_fallback().to_le_bytes();
+                chunk.copy_from_slice(&bytes[..chunk.len()]);
+            }
+        }
+    }
+}
+
+/// Calls `f` with an infallible generator which draws from `rng`.
+/// Returns the result of `f`, or the first error of `rng`.
+///
+/// Consumes exactly the same random numbers as calling `f` with `rng` directly would, up to the first error.
+/// After the first error, `f` still runs to the end on the fallback sequence of [`ErrorCapturingRng`].
+pub fn try_with_rng<R: TryRngCore + ?Sized, X>(
+    rng: &mut R,
+    f: impl FnOnce(&mut ErrorCapturingRng<'_, R>) -> X,
+) -> Result<X, R::Error> {
+    let mut rng = ErrorCapturingRng::new(rng);
+    let value = f(&mut rng);
+    rng.into_result(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use core::num::NonZeroU32;
+
+    use hashbrown::HashTable;
+    use rand::{rngs::StdRng, RngCore, SeedableRng, TryRngCore};
+
+    use super::try_with_rng;
+    use crate::uniform::Uniform;
+    use crate::{CollectionKindness, Kindness};
+
+    const LENGTH: usize = 100;
+
+    #[derive(Debug, PartialEq, Eq)]
+    struct Exhausted;
+
+    impl core::fmt::Display for Exhausted {
+        fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+            f.write_str("exhausted")
+        }
+    }
+
+    /// A generator which fails after a fixed number of calls
+    struct FailingRng {
+        rng: StdRng,
+        remaining: usize,
+    }
+
+    impl FailingRng {
+        fn new(seed: u64, remaining: usize) -> Self {
+            Self {
+                rng: StdRng::seed_from_u64(seed),
+                remaining,
+            }
+        }
+
+        fn draw<X>(&mut self, f: impl FnOnce(&mut StdRng) -> X) -> Result<X, Exhausted> {
+            self.remaining = self.remaining.checked_sub(1).ok_or(Exhausted)?;
+            Ok(f(&mut self.rng))
+        }
+    }
+
+    impl TryRngCore for FailingRng {
+        type Error = Exhausted;
+
+        fn try_next_u32(&mut self) -> Result<u32, Self::Error> {
+            self.draw(RngCore::next_u32)
+        }
+
+        fn try_next_u64(&mut self) -> Result<u64, Self::Error> {
+            self.draw(RngCore::next_u64)
+        }
+
+        fn try_fill_bytes(&mut self, dst: &mut [u8]) -> Result<(), Self::Error> {
+            self.draw(|rng| rng.fill_bytes(dst))
+        }
+    }
+
+    #[test]
+    fn test_try_with_rng_matches_infallible() {
+        for seed in 0..32 {
+            let mut expected = StdRng::seed_from_u64(seed);
+            let mut actual = FailingRng::new(seed, usize::MAX);
+
+            assert_eq!(
+                try_with_rng(&mut actual, |rng| (0..LENGTH).choose_item(rng)),
+                Ok((0..LENGTH).choose_item(&mut expected))
+            );
+            assert_eq!(
+                try_with_rng(&mut actual, |rng| (0..LENGTH)
+                    .choose_max_by_key(rng, |x| x / 10)),
+                Ok((0..LENGTH).choose_max_by_key(&mut expected, |x| x / 10))
+            );
+            assert_eq!(
+                try_with_rng(&mut actual, |rng| (0..LENGTH).choose_multiple(rng, 10)),
+                Ok((0..LENGTH).choose_multiple(&mut expected, 10))
+            );
+            // The generators are still in step
+            assert_eq!(actual.try_next_u64(), Ok(expected.next_u64()));
+        }
+    }
+
+    #[test]
+    fn test_try_with_rng_returns_error() {
+        for remaining in 0..4 {
+            // Both of these need many more than four random numbers
+            let mut rng = FailingRng::new(123, remaining);
+            let result = try_with_rng(&mut rng, |rng| {
+                (0..100_000).filter(|_| true).choose_item(rng)
+            });
+            assert_eq!(result, Err(Exhausted));
+
+            let mut rng = FailingRng::new(123, remaining);
+            let result = try_with_rng(&mut rng, |rng| {
+                (0..1000).filter(|_| true).choose_multiple(rng, 10)
+            });
+            assert_eq!(result, Err(Exhausted));
+        }
+    }
+
+    #[test]
+    fn test_rejection_sampling_finishes_after_error() {
+        // Method D rejects every skip when its uniform values are all zero
+        let mut rng = FailingRng::new(123, 0);
+        let result = try_with_rng(&mut rng, |rng| (0..1000).choose_multiple_ordered(rng, 10));
+        assert_eq!(result, Err(Exhausted));
+
+        // Probing a table whose first bucket is empty never finds an element with an index of zero
+        let mut table = HashTable::new();
+        for x in 0..14_u64 {
+            table.insert_unique(x + 1, x, |y| y + 1);
+        }
+        assert!(table.get_bucket(0).is_none());
+        assert!(table.len() * crate::collection::SPARSE_RATIO >= table.num_buckets());
+        let mut rng = FailingRng::new(123, 0);
+        let result = try_with_rng(&mut rng, |rng| table.choose_item(rng).copied());
+        assert_eq!(result, Err(Exhausted));
+    }
+
+    #[test]
+    fn test_try_next() {
+        for n in [1, 2, 10, 100] {
+            let n = NonZeroU32::new(n).unwrap();
+            let mut expected_uniform = Uniform::new(n);
+            let mut expected = StdRng::seed_from_u64(123);
+            let mut actual_uniform = Uniform::new(n);
+            let mut actual = FailingRng::new(123, usize::MAX);
+            for _ in 0..LENGTH {
+                assert_eq!(
+                    actual_uniform.try_next(&mut actual),
+                    Ok(expected_uniform.next(&mut expected))
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_try_next_error_leaves_state_unchanged() {
+        let n = NonZeroU32::new(10).unwrap();
+        for remaining in 0..4 {
+            let mut uniform = Uniform::new(n);
+            let mut rng = FailingRng::new(123, remaining);
+            let mut drawn = alloc::vec::Vec::new();
+            while let Ok(value) = uniform.try_next(&mut rng) {
+                drawn.push(value);
+            }
+
+            // Retrying with the rest of the stream continues as if the error had not happened
+            let mut retry = StdRng::seed_from_u64(123);
+            for _ in 0..remaining {
+                retry.next_u32();
+            }
+            drawn.push(uniform.next(&mut retry));
+
+            let mut expected_uniform = Uniform::new(n);
+            let mut expected = StdRng::seed_from_u64(123);
+            let expected: alloc::vec::Vec<_> = (0..drawn.len())
+                .map(|_| expected_uniform.next(&mut expected))
+                .collect();
+            assert_eq!(drawn, expected);
+        }
+    }
+}
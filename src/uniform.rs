@@ -3,7 +3,7 @@ use core::{
     u32,
 };
 
-use rand::{Rng, RngCore};
+use rand::{Rng, RngCore, TryRngCore};
 
 /// A uniform distribution where n is a power of two
 #[derive(Debug, Clone, Copy)]
@@ -20,8 +20,8 @@ pub struct PowerOfTwo{
 /// A uniform distribution where n is not a power of two
 #[derive(Debug, Clone, Copy)]
 pub struct NonPowerOfTwo {
-    ///
-    inclusive_upper: u32,
+    /// The largest power of n that fits in a u32
+    exclusive_upper: u32,
     ///
     max_count: u32,
     ///
@@ -32,6 +32,20 @@ pub struct NonPowerOfTwo {
     n: NonZero<u32>,
 }
 
+/// A uniform distribution using Lumbroso's Fast Dice Roller.
+///
+/// Consumes random bits one at a time and uses on average fewer than `log2(n) + 2` bits per value,
+/// which is much less than [`NonPowerOfTwo`] when only one or two values fit into 32 bits.
+#[derive(Debug, Clone, Copy)]
+pub struct FastDiceRoller {
+    ///
+    n: NonZero<u32>,
+    ///
+    chunk: u32,
+    ///
+    chunk_remaining: u32,
+}
+
 impl PowerOfTwo{
     /// Get the next random value in 0..n
     pub fn next(&mut self, rng: &mut impl Rng) -> u32 {
@@ -57,8 +71,8 @@ impl NonPowerOfTwo{
         } else {
             self.counter = self.max_count;
             'random: loop {
-                let next = rng.next_u32() >> self.inclusive_upper.leading_zeros() ;
-                if next <= self.inclusive_upper {
+                let next = rng.next_u32() >> self.exclusive_upper.leading_zeros() ;
+                if next < self.exclusive_upper {
                     self.chunk = next;
                     break 'random;
                 }
@@ -71,12 +85,67 @@ impl NonPowerOfTwo{
     }
 }
 
+impl FastDiceRoller {
+    pub(crate) const fn new(n: NonZeroU32) -> Self {
+        Self {
+            n,
+            chunk: 0,
+            chunk_remaining: 0,
+        }
+    }
+
+    /// Get the next random value in 0..n
+    pub fn next(&mut self, rng: &mut impl Rng) -> u32 {
+        self.next_below(self.n, rng)
+    }
+
+    /// Get a random value in 0..`n` for a different `n`.
+    /// Unused random bits are shared between calls, whatever their bounds.
+    pub fn next_below(&mut self, n: NonZero<u32>, rng: &mut impl Rng) -> u32 {
+        let n = u64::from(n.get());
+        if n == 1 {
+            return 0;
+        }
+        // `v` is the size of the range that `c` is uniformly distributed over
+        let mut v: u64 = 1;
+        let mut c: u64 = 0;
+        loop {
+            v <<= 1;
+            c = (c << 1) | self.next_bit(rng);
+            if v >= n {
+                if c < n {
+                    return c as u32;
+                }
+                // Keep the leftover randomness rather than starting again
+                v -= n;
+                c -= n;
+            }
+        }
+    }
+
+    #[inline]
+    fn next_bit(&mut self, rng: &mut impl Rng) -> u64 {
+        if let Some(new_remaining) = self.chunk_remaining.checked_sub(1) {
+            self.chunk_remaining = new_remaining;
+        } else {
+            self.chunk = rng.next_u32();
+            self.chunk_remaining = u32::BITS - 1;
+        }
+        let bit = self.chunk & 1;
+        self.chunk >>= 1;
+        u64::from(bit)
+    }
+}
+
 ///Uniform Distribution
+#[derive(Debug, Clone, Copy)]
 pub enum Uniform{
     ///
     PowerOfTwo(PowerOfTwo),
     ///
-    NonPowerOfTwo(NonPowerOfTwo)
+    NonPowerOfTwo(NonPowerOfTwo),
+    ///
+    FastDiceRoller(FastDiceRoller),
 }
 
 impl Uniform{
@@ -85,8 +154,36 @@ impl Uniform{
         match self {
             Uniform::PowerOfTwo(x) => x.next(rng),
             Uniform::NonPowerOfTwo(x) => x.next(rng),
+            Uniform::FastDiceRoller(x) => x.next(rng),
         }
     }
+
+    /// Fill `dest` with random values in 0..n.
+    ///
+    /// Gives exactly the same values as calling [`Uniform::next`] for each element in turn,
+    /// packing several values into each random number in the same way, but only chooses the strategy once.
+    pub fn fill(&mut self, rng: &mut impl Rng, dest: &mut [u32]) {
+        match self {
+            Uniform::PowerOfTwo(x) => dest.iter_mut().for_each(|value| *value = x.next(rng)),
+            Uniform::NonPowerOfTwo(x) => dest.iter_mut().for_each(|value| *value = x.next(rng)),
+            Uniform::FastDiceRoller(x) => dest.iter_mut().for_each(|value| *value = x.next(rng)),
+        }
+    }
+
+    /// Get the next random value in 0..n from a fallible generator.
+    /// Returns the first error of the generator, in which case the distribution is left unchanged.
+    pub fn try_next<R: TryRngCore + ?Sized>(&mut self, rng: &mut R) -> Result<u32, R::Error> {
+        let mut next = *self;
+        let value = crate::try_rng::try_with_rng(rng, |rng| next.next(rng))?;
+        *self = next;
+        Ok(value)
+    }
+
+    /// Create a new uniform distribution which uses the [`FastDiceRoller`] strategy.
+    /// This uses fewer random bits than [`Uniform::new`] when `n` is large and not a power of two.
+    pub fn new_fast_dice_roller(n: NonZeroU32) -> Self {
+        Self::FastDiceRoller(FastDiceRoller::new(n))
+    }
     ///Create a new uniform distribution
     pub fn new(n: NonZeroU32)-> Self{
         let u = n.get();
@@ -104,17 +201,17 @@ impl Uniform{
             let bits_used = u32::BITS - u.leading_zeros();
             let log_floor = u32::BITS / bits_used;
 
-            let mut inclusive_upper = u.pow(log_floor);
+            let mut exclusive_upper = u.pow(log_floor);
             let mut max_count = log_floor - 1;
             'count_up: loop {
-                if let Some(new_upper) = inclusive_upper.checked_mul(u) {
-                    inclusive_upper = new_upper;
+                if let Some(new_upper) = exclusive_upper.checked_mul(u) {
+                    exclusive_upper = new_upper;
                     max_count += 1;
                 } else {
                     break 'count_up;
                 }
             }
-            Self::NonPowerOfTwo(NonPowerOfTwo {inclusive_upper,
+            Self::NonPowerOfTwo(NonPowerOfTwo {exclusive_upper,
                 max_count,
                 counter: 0,
                 chunk: 0,
@@ -173,12 +270,145 @@ mod tests {
         insta::assert_snapshot!(summary);
     }
 
+    #[test]
+    pub fn test_generate_fast_dice_roller_10() {
+        let summary = test_generate_with(10, Uniform::new_fast_dice_roller);
+        insta::assert_snapshot!(summary);
+    }
+
+    #[test]
+    pub fn test_generate_fast_dice_roller_100() {
+        let summary = test_generate_with(100, Uniform::new_fast_dice_roller);
+        insta::assert_snapshot!(summary);
+    }
+
+    #[test]
+    pub fn test_generate_fast_dice_roller_1() {
+        let summary = test_generate_with(1, Uniform::new_fast_dice_roller);
+        insta::assert_snapshot!(summary);
+    }
+
+    #[test]
+    pub fn test_generate_fast_dice_roller_16() {
+        let summary = test_generate_with(16, Uniform::new_fast_dice_roller);
+        insta::assert_snapshot!(summary);
+    }
+
+    #[test]
+    pub fn test_fast_dice_roller_bits_used() {
+        for n in [2u32, 3, 10, 100, 1000, 65537, (1 << 20) + 1, 3 << 29, u32::MAX] {
+            let non_zero = NonZeroU32::new(n).unwrap();
+            let fdr = count_rng_calls(Uniform::new_fast_dice_roller(non_zero), n);
+
+            // The fast dice roller uses fewer than log2(n) + 2 bits per value on average
+            // Allow one percent for noise as the bound is tight when n is just above a power of two
+            let max_bits = (RUNS as f64) * (f64::from(n).log2() + 2.0) * 1.01;
+            assert!(((fdr * 32) as f64) < max_bits, "n = {n}: {fdr} calls");
+        }
+    }
+
+    #[test]
+    pub fn test_fast_dice_roller_saves_bits() {
+        for n in [100u32, 1000, 65537, (1 << 20) + 1, 3 << 29] {
+            let non_zero = NonZeroU32::new(n).unwrap();
+            let packed = count_rng_calls(Uniform::new(non_zero), n);
+            let fdr = count_rng_calls(Uniform::new_fast_dice_roller(non_zero), n);
+
+            assert!(fdr < packed, "n = {n}: {fdr} calls vs {packed} calls");
+        }
+    }
+
+    #[test]
+    pub fn test_fast_dice_roller_next_below() {
+        let mut rng = get_rng();
+        let Uniform::FastDiceRoller(mut fdr) = Uniform::new_fast_dice_roller(NonZeroU32::MIN) else {
+            unreachable!()
+        };
+        let mut counts = [0usize; 10];
+        for _ in 0..RUNS {
+            for n in 1..=10u32 {
+                let v = fdr.next_below(NonZeroU32::new(n).unwrap(), &mut rng);
+                assert!(v < n);
+                counts[v as usize] += 1;
+            }
+        }
+        insta::assert_debug_snapshot!(counts);
+    }
+
+    #[test]
+    pub fn test_fill_matches_next() {
+        for n in [1u32, 2, 3, 8, 10, 100, 65537, u32::MAX] {
+            let non_zero = NonZeroU32::new(n).unwrap();
+            for new in [Uniform::new, Uniform::new_fast_dice_roller] {
+                let mut expected_uniform = new(non_zero);
+                let mut expected_rng = get_rng();
+                let expected: Vec<u32> = (0..100)
+                    .map(|_| expected_uniform.next(&mut expected_rng))
+                    .collect();
+
+                let mut uniform = new(non_zero);
+                let mut rng = get_rng();
+                let mut actual = [0u32; 100];
+                // Uneven chunks share the packed bits
+                let (first, second) = actual.split_at_mut(7);
+                uniform.fill(&mut rng, first);
+                uniform.fill(&mut rng, second);
+
+                assert_eq!(actual.as_slice(), expected.as_slice(), "n = {n}");
+                assert_eq!(rng.count, expected_rng.count);
+            }
+        }
+    }
+
+    #[test]
+    pub fn test_non_power_of_two_rejects_exclusive_upper() {
+        // 3^20 is the largest power of 3 which fits in a u32, so it is outside the chunk range and must be rejected
+        let mut rng = SequenceRng([3u32.pow(20), 1].into_iter());
+        let mut uniform = Uniform::new(NonZeroU32::new(3).unwrap());
+        assert_eq!(uniform.next(&mut rng), 1);
+    }
+
+    /// Returns the values of a fixed sequence
+    struct SequenceRng<I: Iterator<Item = u32>>(I);
+
+    impl<I: Iterator<Item = u32>> RngCore for SequenceRng<I> {
+        fn next_u32(&mut self) -> u32 {
+            self.0.next().unwrap()
+        }
+
+        fn next_u64(&mut self) -> u64 {
+            // Low half first, like `rand_core::impls::next_u64_via_u32`
+            let low = u64::from(self.next_u32());
+            (u64::from(self.next_u32()) << 32) | low
+        }
+
+        fn fill_bytes(&mut self, dest: &mut [u8]) {
+            for chunk in dest.chunks_mut(4) {
+                let bytes = self.next_u32().to_le_bytes();
+                chunk.copy_from_slice(&bytes[..chunk.len()]);
+            }
+        }
+    }
+
+    fn count_rng_calls(mut uniform: Uniform, n: u32) -> usize {
+        let mut rng = get_rng();
+        for _ in 0..RUNS {
+            assert!(uniform.next(&mut rng) < n);
+        }
+        rng.count
+    }
+
     #[must_use]
     pub fn test_generate(buckets: usize) -> String {
+        test_generate_with(buckets, Uniform::new)
+    }
+
+    #[must_use]
+    pub fn test_generate_with(buckets: usize, new: impl Fn(NonZeroU32) -> Uniform) -> String {
         let mut counts: Vec<usize> = std::iter::repeat(0).take(buckets).collect();
         let mut rng = get_rng();
 
-        let mut uniform = Uniform::new(NonZeroU32::new(buckets as u32).unwrap());
+        let mut uniform = new(NonZeroU32::new(buckets as u32).unwrap());
         for _ in 0..RUNS {
             let v = uniform.next(&mut rng);
 
@@ -1,25 +1,40 @@
-#[cfg(any(test, feature = "std"))]
+#[cfg(any(test, feature = "hashbrown"))]
 pub mod iterators {
     use core::hash::Hash;
     use core::iter::FusedIterator;
     use hashbrown::{hash_map::*, HashTable};
 
+    use crate::counter::Counter;
+
     #[derive(Debug)]
-    pub struct Unique<Item, A: allocator_api2::alloc::Allocator + Clone> {
-        table: hashbrown::hash_table::IntoIter<(Item, usize), A>,
+    pub struct Unique<Item, A: allocator_api2::alloc::Allocator + Clone, C: Counter = usize> {
+        table: hashbrown::hash_table::IntoIter<(Item, C), A>,
     }
 
-    impl<Item, A: allocator_api2::alloc::Allocator + Clone> Unique<Item, A> {
-        pub fn new(table: hashbrown::hash_table::IntoIter<(Item, usize), A>) -> Self {
+    impl<Item, A: allocator_api2::alloc::Allocator + Clone, C: Counter> Unique<Item, A, C> {
+        pub fn new(table: hashbrown::hash_table::IntoIter<(Item, C), A>) -> Self {
             Self { table }
         }
+
+        /// The remaining elements along with the number of duplicates each was chosen from
+        pub(crate) fn into_counted(self) -> hashbrown::hash_table::IntoIter<(Item, C), A> {
+            self.table
+        }
     }
 
-    impl<Item, A: allocator_api2::alloc::Allocator + Clone> ExactSizeIterator for Unique<Item, A> {}
+    impl<Item, A: allocator_api2::alloc::Allocator + Clone, C: Counter> ExactSizeIterator
+        for Unique<Item, A, C>
+    {
+    }
 
-    impl<Item, A: allocator_api2::alloc::Allocator + Clone> FusedIterator for Unique<Item, A> {}
+    impl<Item, A: allocator_api2::alloc::Allocator + Clone, C: Counter> FusedIterator
+        for Unique<Item, A, C>
+    {
+    }
 
-    impl<Item, A: allocator_api2::alloc::Allocator + Clone> Iterator for Unique<Item, A> {
+    impl<Item, A: allocator_api2::alloc::Allocator + Clone, C: Counter> Iterator
+        for Unique<Item, A, C>
+    {
         type Item = Item;
 
         fn next(&mut self) -> Option<Self::Item> {
@@ -51,28 +66,40 @@ pub mod iterators {
 
     #[derive(Debug)]
     /// An iterator adapter to filter out duplicate elements by a key.
-    pub struct UniqueByKey<K: Eq + Hash, Item, A: allocator_api2::alloc::Allocator + Clone> {
-        map: IntoValues<K, (Item, usize), A>,
+    pub struct UniqueByKey<
+        K: Eq + Hash,
+        Item,
+        A: allocator_api2::alloc::Allocator + Clone,
+        C: Counter = usize,
+    > {
+        map: IntoValues<K, (Item, C), A>,
     }
 
-    impl<K: Eq + Hash, Item, A: allocator_api2::alloc::Allocator + Clone> UniqueByKey<K, Item, A> {
-        pub fn new(map: IntoValues<K, (Item, usize), A>) -> Self {
+    impl<K: Eq + Hash, Item, A: allocator_api2::alloc::Allocator + Clone, C: Counter>
+        UniqueByKey<K, Item, A, C>
+    {
+        pub fn new(map: IntoValues<K, (Item, C), A>) -> Self {
             Self { map }
         }
+
+        /// The remaining elements along with the number of duplicates each was chosen from
+        pub(crate) fn into_counted(self) -> IntoValues<K, (Item, C), A> {
+            self.map
+        }
     }
 
-    impl<K: Eq + Hash, Item, A: allocator_api2::alloc::Allocator + Clone> ExactSizeIterator
-        for UniqueByKey<K, Item, A>
+    impl<K: Eq + Hash, Item, A: allocator_api2::alloc::Allocator + Clone, C: Counter>
+        ExactSizeIterator for UniqueByKey<K, Item, A, C>
     {
     }
 
-    impl<K: Eq + Hash, Item, A: allocator_api2::alloc::Allocator + Clone> FusedIterator
-        for UniqueByKey<K, Item, A>
+    impl<K: Eq + Hash, Item, A: allocator_api2::alloc::Allocator + Clone, C: Counter> FusedIterator
+        for UniqueByKey<K, Item, A, C>
     {
     }
 
-    impl<K: Eq + Hash, Item, A: allocator_api2::alloc::Allocator + Clone> Iterator
-        for UniqueByKey<K, Item, A>
+    impl<K: Eq + Hash, Item, A: allocator_api2::alloc::Allocator + Clone, C: Counter> Iterator
+        for UniqueByKey<K, Item, A, C>
     {
         type Item = Item;
 
@@ -103,3 +130,169 @@ pub mod iterators {
         }
     }
 }
+
+#[cfg(any(test, feature = "hashbrown"))]
+mod chooser {
+    use core::hash::{BuildHasher, Hash};
+
+    use hashbrown::hash_table::{Entry, HashTable};
+    use rand::Rng;
+
+    use super::iterators::Unique;
+    use crate::coin_flipper::CoinFlipper;
+    use crate::counter::Counter;
+
+    /// Chooses a random representative of each set of duplicates from elements which arrive in batches.
+    ///
+    /// Each representative is chosen uniformly from all of its duplicates seen so far,
+    /// so extending or merging in any order gives the same distribution as a single call to
+    /// [`Kindness::choose_unique_with_hasher_in`](crate::Kindness::choose_unique_with_hasher_in) over everything.
+    /// Duplicates are detected using hash and equality.
+    ///
+    /// ```
+    /// use kindness::*;
+    /// use rand::SeedableRng;
+    ///
+    /// let mut rng = rand::rngs::StdRng::seed_from_u64(123);
+    /// let mut chooser = UniqueChooser::new();
+    /// chooser.extend(["a", "b", "a"], &mut rng);
+    /// chooser.extend(["b", "c"], &mut rng);
+    /// assert_eq!(chooser.len(), 3);
+    /// ```
+    #[derive(Debug, Clone)]
+    pub struct UniqueChooser<
+        T,
+        S,
+        A: allocator_api2::alloc::Allocator + Clone = allocator_api2::alloc::Global,
+        C: Counter = usize,
+    > {
+        table: HashTable<(T, C), A>,
+        hash_builder: S,
+    }
+
+    #[cfg(any(test, feature = "std"))]
+    impl<T: Hash + Eq> UniqueChooser<T, std::collections::hash_map::RandomState> {
+        /// Creates an empty chooser which uses the global allocator and the default hasher which is safe from HashDos attacks.
+        #[must_use]
+        pub fn new() -> Self {
+            Self::new_in(
+                std::collections::hash_map::RandomState::new(),
+                allocator_api2::alloc::Global,
+            )
+        }
+    }
+
+    #[cfg(any(test, feature = "std"))]
+    impl<T: Hash + Eq> Default for UniqueChooser<T, std::collections::hash_map::RandomState> {
+        fn default() -> Self {
+            Self::new()
+        }
+    }
+
+    impl<T: Hash + Eq, S: BuildHasher, A: allocator_api2::alloc::Allocator + Clone, C: Counter>
+        UniqueChooser<T, S, A, C>
+    {
+        /// Creates an empty chooser with the given `BuildHasher` and `Allocator`.
+        pub fn new_in(hash_builder: S, alloc: A) -> Self {
+            Self {
+                table: HashTable::new_in(alloc),
+                hash_builder,
+            }
+        }
+
+        /// The number of unique elements.
+        #[must_use]
+        pub fn len(&self) -> usize {
+            self.table.len()
+        }
+
+        /// Returns `true` if no elements have been added.
+        #[must_use]
+        pub fn is_empty(&self) -> bool {
+            self.table.is_empty()
+        }
+
+        /// Adds a batch of elements.
+        /// Each element replaces the representative of its duplicates with probability one over the number of those duplicates.
+        pub fn extend<I: IntoIterator<Item = T>, R: Rng>(&mut self, batch: I, rng: &mut R) {
+            let mut coin_flipper = CoinFlipper::new(rng);
+            for item in batch {
+                match self.entry(&item) {
+                    Entry::Occupied(mut occupied_entry) => {
+                        let new_count = occupied_entry.get().1.saturating_add(C::ONE);
+                        occupied_entry.get_mut().1 = new_count;
+
+                        if coin_flipper.gen_ratio_one_over(new_count) {
+                            //We have randomly decided to change the key to the new item
+                            occupied_entry.get_mut().0 = item;
+                        }
+                    }
+                    Entry::Vacant(vacant_entry) => {
+                        vacant_entry.insert((item, C::ONE));
+                    }
+                }
+            }
+        }
+
+        /// Adds the elements of another chooser.
+        /// Where both have a representative of the same duplicates, each is kept with probability proportional to
+        /// the number of duplicates it was chosen from.
+        pub fn merge<R: Rng>(&mut self, other: Self, rng: &mut R) {
+            let mut coin_flipper = CoinFlipper::new(rng);
+            for (item, count) in other.table {
+                match self.entry(&item) {
+                    Entry::Occupied(mut occupied_entry) => {
+                        let new_count = occupied_entry.get().1.saturating_add(count);
+                        occupied_entry.get_mut().1 = new_count;
+
+                        if coin_flipper.gen_ratio(count, new_count) {
+                            occupied_entry.get_mut().0 = item;
+                        }
+                    }
+                    Entry::Vacant(vacant_entry) => {
+                        vacant_entry.insert((item, count));
+                    }
+                }
+            }
+        }
+
+        /// Removes all elements but keeps the allocated memory for reuse.
+        pub fn clear(&mut self) {
+            self.table.clear();
+        }
+
+        /// Removes and returns the chosen elements but keeps the allocated memory for reuse.
+        pub fn drain(&mut self) -> impl ExactSizeIterator<Item = T> + '_ {
+            self.table.drain().map(|(item, _)| item)
+        }
+
+        /// Returns the chosen elements in an arbitrary order.
+        pub fn iter(&self) -> impl ExactSizeIterator<Item = &T> + '_ {
+            self.table.iter().map(|(item, _)| item)
+        }
+
+        fn entry(&mut self, item: &T) -> Entry<'_, (T, C), A> {
+            let hash_builder = &self.hash_builder;
+            let hash = hash_builder.hash_one(item);
+            self.table.entry(
+                hash,
+                |(other, _)| item.eq(other),
+                |(i, _)| hash_builder.hash_one(i),
+            )
+        }
+    }
+
+    impl<T, S, A: allocator_api2::alloc::Allocator + Clone, C: Counter> IntoIterator
+        for UniqueChooser<T, S, A, C>
+    {
+        type Item = T;
+        type IntoIter = Unique<T, A, C>;
+
+        fn into_iter(self) -> Self::IntoIter {
+            Unique::new(self.table.into_iter())
+        }
+    }
+}
+
+#[cfg(any(test, feature = "hashbrown"))]
+pub use chooser::UniqueChooser;
@@ -0,0 +1,578 @@
+//! The algorithms of kindness 0.5.
+//!
+//! Use [`v0_5::Kindness`](Kindness) instead of [`crate::Kindness`] to reproduce the results of kindness 0.5 for the same seed.
+//! Methods whose output has not changed since 0.5 use the current implementation,
+//! and the tests pin every method to values produced by kindness 0.5.
+
+use core::cmp::Ordering;
+use core::hash::{BuildHasher, Hash};
+
+use rand::Rng;
+
+use crate::coin_flipper::CoinFlipper;
+
+impl<T: Iterator + Sized> Kindness for T {}
+
+/// The `choose_item` algorithm of kindness 0.4 and 0.5.
+/// These versions differ only in how `gen_index` samples an index in `0..ubound`.
+pub(crate) fn choose_item<I: Iterator, R: Rng, G: Fn(&mut R, usize) -> usize>(
+    mut iterator: I,
+    rng: &mut R,
+    gen_index: G,
+) -> Option<I::Item> {
+    let (mut lower, mut upper) = iterator.size_hint();
+    let mut result = None;
+
+    if upper == Some(lower) {
+        return if lower == 0 {
+            None
+        } else {
+            iterator.nth(gen_index(rng, lower))
+        };
+    }
+
+    let mut coin_flipper = CoinFlipper::new(rng);
+    let mut consumed: usize = 0;
+
+    loop {
+        if lower > 1 {
+            let ix = gen_index(coin_flipper.rng, lower + consumed);
+            let skip = if ix < lower {
+                result = iterator.nth(ix);
+                lower - (ix + 1)
+            } else {
+                lower
+            };
+            if upper == Some(lower) {
+                return result;
+            }
+            consumed += lower;
+            if skip > 0 {
+                iterator.nth(skip - 1);
+            }
+        } else {
+            consumed += 1;
+            let skip = coin_flipper.try_skip(consumed as u32) as usize;
+            let elem = iterator.nth(skip);
+            if elem.is_none() {
+                return result;
+            }
+            consumed += skip;
+
+            if coin_flipper.gen_ratio_one_over(consumed) {
+                result = elem;
+            }
+        }
+
+        let hint = iterator.size_hint();
+        lower = hint.0;
+        upper = hint.1;
+    }
+}
+
+// Sample a number uniformly between 0 and `ubound` as rand 0.9 does. Uses 32-bit sampling where
+// possible, primarily in order to produce the same output on 32-bit and 64-bit
+// platforms.
+#[inline]
+fn gen_index<R: Rng + ?Sized>(rng: &mut R, ubound: usize) -> usize {
+    if ubound <= (u32::MAX as usize) {
+        rng.random_range(0..ubound as u32) as usize
+    } else {
+        rng.random_range(0..ubound)
+    }
+}
+
+/// The [`crate::Kindness`] methods as they were in kindness 0.5.
+pub trait Kindness: Iterator
+where
+    Self: Sized,
+{
+    /// Return a random element of the iterator.
+    /// See [`crate::Kindness::choose_item`].
+    #[inline]
+    fn choose_item<R: Rng>(self, rng: &mut R) -> Option<Self::Item> {
+        choose_item(self, rng, gen_index)
+    }
+
+    /// Returns a random maximum element.
+    /// See [`crate::Kindness::choose_max`].
+    fn choose_max<R: Rng>(self, rng: &mut R) -> Option<Self::Item>
+    where
+        Self::Item: Ord,
+    {
+        crate::Kindness::choose_max(self, rng)
+    }
+
+    /// Returns a random element that gives the maximum value from the specified function.
+    /// See [`crate::Kindness::choose_max_by_key`].
+    fn choose_max_by_key<B: Ord, R: Rng, F: FnMut(&Self::Item) -> B>(
+        self,
+        rng: &mut R,
+        f: F,
+    ) -> Option<Self::Item> {
+        crate::Kindness::choose_max_by_key(self, rng, f)
+    }
+
+    /// Returns a random maximum element with respect to the specified comparison function.
+    /// See [`crate::Kindness::choose_max_by`].
+    fn choose_max_by<R: Rng, F: FnMut(&Self::Item, &Self::Item) -> Ordering>(
+        self,
+        rng: &mut R,
+        compare: F,
+    ) -> Option<Self::Item>
+    where
+        Self::Item: Ord,
+    {
+        crate::Kindness::choose_max_by(self, rng, compare)
+    }
+
+    /// Return a random minimum element of the iterator.
+    /// See [`crate::Kindness::choose_min`].
+    fn choose_min<R: Rng>(self, rng: &mut R) -> Option<Self::Item>
+    where
+        Self::Item: Ord,
+    {
+        crate::Kindness::choose_min(self, rng)
+    }
+
+    /// Returns a random element that gives the minimum value from the specified function.
+    /// See [`crate::Kindness::choose_min_by_key`].
+    fn choose_min_by_key<B: Ord, R: Rng, F: FnMut(&Self::Item) -> B>(
+        self,
+        rng: &mut R,
+        f: F,
+    ) -> Option<Self::Item> {
+        crate::Kindness::choose_min_by_key(self, rng, f)
+    }
+
+    /// Returns a random minimum element with respect to the specified comparison function.
+    /// See [`crate::Kindness::choose_min_by`].
+    fn choose_min_by<R: Rng, F: FnMut(&Self::Item, &Self::Item) -> Ordering>(
+        self,
+        rng: &mut R,
+        compare: F,
+    ) -> Option<Self::Item>
+    where
+        Self::Item: Ord,
+    {
+        crate::Kindness::choose_min_by(self, rng, compare)
+    }
+
+    /// Returns an iterator over unique elements of this iterator.
+    /// See [`crate::Kindness::choose_unique`].
+    #[cfg(any(test, all(feature = "hashbrown", feature = "std")))]
+    fn choose_unique<R: Rng>(
+        self,
+        rng: &mut R,
+    ) -> crate::unique::iterators::Unique<Self::Item, allocator_api2::alloc::Global>
+    where
+        Self::Item: Hash + Eq,
+    {
+        crate::Kindness::choose_unique(self, rng)
+    }
+
+    /// Returns an iterator over unique elements of this iterator.
+    /// See [`crate::Kindness::choose_unique_with_hasher_in`].
+    #[cfg(any(test, feature = "hashbrown"))]
+    fn choose_unique_with_hasher_in<
+        R: Rng,
+        S: BuildHasher,
+        A: allocator_api2::alloc::Allocator + Clone,
+    >(
+        self,
+        rng: &mut R,
+        hash_builder: S,
+        alloc: A,
+    ) -> crate::unique::iterators::Unique<Self::Item, A>
+    where
+        Self::Item: Hash + Eq,
+    {
+        crate::Kindness::choose_unique_with_hasher_in(self, rng, hash_builder, alloc)
+    }
+
+    /// Returns an iterator over unique elements of this iterator.
+    /// See [`crate::Kindness::choose_unique_by_key`].
+    #[cfg(any(test, all(feature = "hashbrown", feature = "std")))]
+    fn choose_unique_by_key<R: Rng, K: Eq + Hash, F: FnMut(&Self::Item) -> K>(
+        self,
+        rng: &mut R,
+        get_key: F,
+    ) -> crate::unique::iterators::UniqueByKey<K, Self::Item, allocator_api2::alloc::Global> {
+        crate::Kindness::choose_unique_by_key(self, rng, get_key)
+    }
+
+    /// Returns an iterator over unique elements of this iterator.
+    /// See [`crate::Kindness::choose_unique_by_key_with_hasher_in`].
+    #[cfg(any(test, feature = "hashbrown"))]
+    fn choose_unique_by_key_with_hasher_in<
+        R: Rng,
+        K: Eq + Hash,
+        F: FnMut(&Self::Item) -> K,
+        S: BuildHasher,
+        A: allocator_api2::alloc::Allocator + Clone,
+    >(
+        self,
+        rng: &mut R,
+        get_key: F,
+        hash_builder: S,
+        alloc: A,
+    ) -> crate::unique::iterators::UniqueByKey<K, Self::Item, A> {
+        crate::Kindness::choose_unique_by_key_with_hasher_in(self, rng, get_key, hash_builder, alloc)
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use core::hash::BuildHasherDefault;
+    use std::hash::DefaultHasher;
+
+    use allocator_api2::alloc::Global;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use super::Kindness;
+
+    // These values were produced by kindness 0.5.0 with `StdRng::seed_from_u64(seed)` for seeds 0..32
+
+    #[test]
+    fn test_choose_item_exact_size() {
+        let results: Vec<usize> = (0..32)
+            .map(|seed| (0..100usize).choose_item(&mut StdRng::seed_from_u64(seed)).unwrap())
+            .collect();
+        assert_eq!(
+            results,
+            [
+                80, 82, 8, 64, 69, 17, 12, 41, 63, 59, 38, 22, 42, 58, 84, 82, 92, 54, 30, 77, 48,
+                83, 94, 13, 62, 62, 27, 95, 43, 99, 37, 20
+            ]
+        );
+    }
+
+    #[test]
+    fn test_choose_item_windowed() {
+        let results: Vec<usize> = (0..32)
+            .map(|seed| {
+                WindowHinted(0..100usize, 7)
+                    .choose_item(&mut StdRng::seed_from_u64(seed))
+                    .unwrap()
+            })
+            .collect();
+        assert_eq!(
+            results,
+            [
+                90, 34, 21, 32, 99, 21, 76, 40, 11, 22, 55, 10, 13, 4, 53, 5, 75, 56, 15, 32, 76,
+                30, 38, 95, 65, 48, 44, 73, 49, 10, 66, 46
+            ]
+        );
+    }
+
+    #[test]
+    fn test_choose_item_unhinted() {
+        let results: Vec<usize> = (0..32)
+            .map(|seed| {
+                (0..100usize)
+                    .filter(|_| true)
+                    .choose_item(&mut StdRng::seed_from_u64(seed))
+                    .unwrap()
+            })
+            .collect();
+        assert_eq!(
+            results,
+            [
+                63, 33, 91, 19, 56, 47, 86, 68, 54, 92, 23, 48, 24, 84, 44, 87, 70, 13, 33, 62, 87,
+                46, 23, 61, 71, 17, 21, 74, 28, 73, 1, 10
+            ]
+        );
+    }
+
+    #[test]
+    #[cfg(target_pointer_width = "64")]
+    fn test_choose_item_large() {
+        let results: Vec<usize> = (0..32)
+            .map(|seed| {
+                (0..(u32::MAX as usize) * 3)
+                    .choose_item(&mut StdRng::seed_from_u64(seed))
+                    .unwrap()
+            })
+            .collect();
+        assert_eq!(
+            results,
+            [
+                9420324630,
+                12553047851,
+                3831014162,
+                1226761147,
+                6867693810,
+                4408266266,
+                4312603163,
+                390636220,
+                4567451913,
+                8332548311,
+                1686358494,
+                3555726632,
+                6216884228,
+                6929303613,
+                7227380481,
+                7774678713,
+                11029559667,
+                8575223657,
+                12626350248,
+                12772108736,
+                5764663806,
+                956982179,
+                736243022,
+                2734766003,
+                5249382522,
+                8309044529,
+                3681709265,
+                2716747697,
+                5799456839,
+                2780604254,
+                12465837484,
+                3391917239
+            ]
+        );
+    }
+
+    #[test]
+    fn test_choose_max_by_key() {
+        let results: Vec<usize> = (0..32)
+            .map(|seed| {
+                (0..100usize)
+                    .choose_max_by_key(&mut StdRng::seed_from_u64(seed), |x| x / 10)
+                    .unwrap()
+            })
+            .collect();
+        assert_eq!(
+            results,
+            [
+                93, 93, 91, 92, 94, 96, 95, 90, 90, 93, 96, 95, 90, 99, 95, 90, 94, 95, 95, 97, 91,
+                91, 92, 91, 96, 93, 96, 97, 97, 97, 92, 96
+            ]
+        );
+    }
+
+    #[test]
+    fn test_choose_max_and_min() {
+        let compare = |a: &usize, b: &usize| (a / 10).cmp(&(b / 10));
+        let results: Vec<[usize; 5]> = (0..32)
+            .map(|seed| {
+                let rng = &mut StdRng::seed_from_u64(seed);
+                [
+                    (0..100).map(Tens).choose_max(rng).unwrap().0,
+                    (0..100usize).choose_max_by(rng, compare).unwrap(),
+                    (0..100).map(Tens).choose_min(rng).unwrap().0,
+                    (0..100usize).choose_min_by_key(rng, |x| x / 10).unwrap(),
+                    (0..100usize).choose_min_by(rng, compare).unwrap(),
+                ]
+            })
+            .collect();
+        assert_eq!(
+            results,
+            [
+                [93, 92, 2, 8, 6],
+                [93, 96, 4, 0, 7],
+                [91, 93, 3, 8, 4],
+                [92, 96, 6, 8, 1],
+                [94, 96, 7, 1, 8],
+                [96, 96, 9, 1, 9],
+                [95, 99, 7, 3, 9],
+                [90, 91, 0, 5, 3],
+                [90, 99, 3, 2, 8],
+                [93, 94, 9, 2, 8],
+                [96, 93, 3, 2, 6],
+                [95, 90, 4, 6, 9],
+                [90, 98, 4, 4, 2],
+                [99, 99, 1, 6, 0],
+                [95, 90, 6, 4, 4],
+                [90, 97, 8, 7, 7],
+                [94, 97, 5, 9, 9],
+                [95, 94, 9, 1, 1],
+                [95, 96, 3, 9, 1],
+                [97, 98, 5, 3, 9],
+                [91, 94, 1, 3, 2],
+                [91, 94, 2, 8, 4],
+                [92, 90, 1, 7, 4],
+                [91, 94, 2, 5, 2],
+                [96, 91, 2, 6, 1],
+                [93, 91, 5, 4, 2],
+                [96, 91, 3, 6, 2],
+                [97, 94, 0, 4, 7],
+                [97, 94, 0, 6, 0],
+                [97, 93, 5, 7, 5],
+                [92, 94, 8, 4, 3],
+                [96, 90, 7, 4, 5],
+            ]
+        );
+    }
+
+    #[test]
+    fn test_choose_unique() {
+        // The order of the results depends on the hasher, but which duplicates are chosen does not
+        let expected = [
+                [3, 12, 20],
+                [8, 13, 25],
+                [6, 14, 26],
+                [7, 13, 26],
+                [3, 15, 25],
+                [1, 12, 21],
+                [2, 11, 21],
+                [1, 10, 27],
+                [9, 14, 21],
+                [2, 11, 22],
+                [4, 14, 28],
+                [8, 16, 22],
+                [7, 16, 21],
+                [6, 17, 29],
+                [0, 14, 23],
+                [7, 12, 26],
+                [0, 14, 25],
+                [6, 14, 23],
+                [9, 14, 21],
+                [3, 13, 24],
+                [1, 16, 29],
+                [0, 18, 24],
+                [5, 15, 25],
+                [2, 13, 22],
+                [3, 10, 23],
+                [2, 19, 27],
+                [9, 10, 27],
+                [9, 16, 29],
+                [1, 15, 22],
+                [0, 12, 23],
+                [1, 10, 22],
+                [4, 14, 24],
+            ];
+        for with_hasher in [false, true] {
+            let results: Vec<Vec<usize>> = (0..32)
+                .map(|seed| {
+                    let rng = &mut StdRng::seed_from_u64(seed);
+                    let iterator = (0..30).map(Tens);
+                    let mut unique: Vec<usize> = if with_hasher {
+                        iterator
+                            .choose_unique_with_hasher_in(
+                                rng,
+                                BuildHasherDefault::<DefaultHasher>::default(),
+                                Global,
+                            )
+                            .map(|x| x.0)
+                            .collect()
+                    } else {
+                        iterator.choose_unique(rng).map(|x| x.0).collect()
+                    };
+                    unique.sort_unstable();
+                    unique
+                })
+                .collect();
+            assert_eq!(results, expected);
+        }
+    }
+
+    #[test]
+    fn test_choose_unique_by_key() {
+        let expected = [
+                [3, 12, 20],
+                [8, 13, 25],
+                [6, 14, 26],
+                [7, 13, 26],
+                [3, 15, 25],
+                [1, 12, 21],
+                [2, 11, 21],
+                [1, 10, 27],
+                [9, 14, 21],
+                [2, 11, 22],
+                [4, 14, 28],
+                [8, 16, 22],
+                [7, 16, 21],
+                [6, 17, 29],
+                [0, 14, 23],
+                [7, 12, 26],
+                [0, 14, 25],
+                [6, 14, 23],
+                [9, 14, 21],
+                [3, 13, 24],
+                [1, 16, 29],
+                [0, 18, 24],
+                [5, 15, 25],
+                [2, 13, 22],
+                [3, 10, 23],
+                [2, 19, 27],
+                [9, 10, 27],
+                [9, 16, 29],
+                [1, 15, 22],
+                [0, 12, 23],
+                [1, 10, 22],
+                [4, 14, 24],
+            ];
+        for with_hasher in [false, true] {
+            let results: Vec<Vec<usize>> = (0..32)
+                .map(|seed| {
+                    let rng = &mut StdRng::seed_from_u64(seed);
+                    let key = |x: &usize| x / 10;
+                    let mut unique: Vec<usize> = if with_hasher {
+                        (0..30)
+                            .choose_unique_by_key_with_hasher_in(
+                                rng,
+                                key,
+                                BuildHasherDefault::<DefaultHasher>::default(),
+                                Global,
+                            )
+                            .collect()
+                    } else {
+                        (0..30).choose_unique_by_key(rng, key).collect()
+                    };
+                    unique.sort_unstable();
+                    unique
+                })
+                .collect();
+            assert_eq!(results, expected);
+        }
+    }
+
+    pub(crate) struct WindowHinted<I: ExactSizeIterator>(pub I, pub usize);
+
+    impl<I: ExactSizeIterator> Iterator for WindowHinted<I> {
+        type Item = I::Item;
+
+        fn next(&mut self) -> Option<Self::Item> {
+            self.0.next()
+        }
+
+        fn nth(&mut self, n: usize) -> Option<Self::Item> {
+            self.0.nth(n)
+        }
+
+        fn size_hint(&self) -> (usize, Option<usize>) {
+            (core::cmp::min(self.0.len(), self.1), None)
+        }
+    }
+
+    /// A number which is only compared and hashed by its tens digit
+    #[derive(Debug, Clone, Copy)]
+    pub(crate) struct Tens(pub usize);
+
+    impl PartialEq for Tens {
+        fn eq(&self, other: &Self) -> bool {
+            self.0 / 10 == other.0 / 10
+        }
+    }
+
+    impl Eq for Tens {}
+
+    impl PartialOrd for Tens {
+        fn partial_cmp(&self, other: &Self) -> Option<core::cmp::Ordering> {
+            Some(self.cmp(other))
+        }
+    }
+
+    impl Ord for Tens {
+        fn cmp(&self, other: &Self) -> core::cmp::Ordering {
+            (self.0 / 10).cmp(&(other.0 / 10))
+        }
+    }
+
+    impl core::hash::Hash for Tens {
+        fn hash<H: core::hash::Hasher>(&self, state: &mut H) {
+            (self.0 / 10).hash(state);
+        }
+    }
+}
@@ -0,0 +1,335 @@
+use core::cmp::Ordering;
+use core::hash::{BuildHasher, Hash};
+use core::marker::PhantomData;
+
+use rand::Rng;
+
+use crate::counter::Counter;
+use crate::Chosen;
+
+/// An iterator whose [`Kindness`](crate::Kindness) methods count elements with `C` instead of `usize`.
+///
+/// Created by [`Kindness::wide_counter`](crate::Kindness::wide_counter).
+/// Each method has the same distribution as the [`Kindness`](crate::Kindness) method of the same name,
+/// except that it stays exactly uniform until `C::MAX` elements have been seen.
+#[derive(Debug, Clone)]
+#[must_use]
+pub struct WideCounter<I: Iterator, C: Counter> {
+    iterator: I,
+    phantom: PhantomData<C>,
+}
+
+impl<I: Iterator, C: Counter> WideCounter<I, C> {
+    pub(crate) const fn new(iterator: I) -> Self {
+        Self {
+            iterator,
+            phantom: PhantomData,
+        }
+    }
+
+    /// Return a random element of the iterator.
+    /// Returns none if the iterator is empty.
+    /// Will iterate the entire enumerable unless it has a size hint which indicates an exact length.
+    pub fn choose_item<R: Rng>(self, rng: &mut R) -> Option<I::Item> {
+        crate::choose_item::<I, R, C>(self.iterator, rng)
+    }
+
+    /// Returns a random maximum element.
+    ///
+    /// If the iterator is empty, [`None`] is returned.
+    pub fn choose_max<R: Rng>(self, rng: &mut R) -> Option<I::Item>
+    where
+        I::Item: Ord,
+    {
+        self.choose_max_by(rng, Ord::cmp)
+    }
+
+    /// Returns a random element that gives the maximum value from the
+    /// specified function.
+    /// If the iterator is empty, [`None`] is returned.
+    pub fn choose_max_by_key<B: Ord, R: Rng, F: FnMut(&I::Item) -> B>(
+        self,
+        rng: &mut R,
+        f: F,
+    ) -> Option<I::Item> {
+        crate::choose_best_by_key::<I, B, R, F, C, true>(self.iterator, rng, f)
+    }
+
+    /// Returns a random maximum element with respect to the specified comparison function.
+    ///
+    /// If the iterator is empty, [`None`] is returned.
+    pub fn choose_max_by<R: Rng, F: FnMut(&I::Item, &I::Item) -> Ordering>(
+        self,
+        rng: &mut R,
+        compare: F,
+    ) -> Option<I::Item>
+    where
+        I::Item: Ord,
+    {
+        crate::choose_best_by::<I, R, F, C, true>(self.iterator, rng, compare)
+    }
+
+    /// Return a random minimum element of the iterator.
+    /// Returns none if the iterator is empty.
+    pub fn choose_min<R: Rng>(self, rng: &mut R) -> Option<I::Item>
+    where
+        I::Item: Ord,
+    {
+        self.choose_min_by(rng, Ord::cmp)
+    }
+
+    /// Returns a random element that gives the minimum value from the
+    /// specified function.
+    /// If the iterator is empty, [`None`] is returned.
+    pub fn choose_min_by_key<B: Ord, R: Rng, F: FnMut(&I::Item) -> B>(
+        self,
+        rng: &mut R,
+        f: F,
+    ) -> Option<I::Item> {
+        crate::choose_best_by_key::<I, B, R, F, C, false>(self.iterator, rng, f)
+    }
+
+    /// Returns a random minimum element with respect to the specified comparison function.
+    ///
+    /// If the iterator is empty, [`None`] is returned.
+    pub fn choose_min_by<R: Rng, F: FnMut(&I::Item, &I::Item) -> Ordering>(
+        self,
+        rng: &mut R,
+        compare: F,
+    ) -> Option<I::Item>
+    where
+        I::Item: Ord,
+    {
+        crate::choose_best_by::<I, R, F, C, false>(self.iterator, rng, compare)
+    }
+
+    /// Like [`choose_max`](Self::choose_max) but also returns the number of elements tied for maximum
+    /// and the total number of elements.
+    pub fn choose_max_with_stats<R: Rng>(self, rng: &mut R) -> Option<Chosen<I::Item, (), C>>
+    where
+        I::Item: Ord,
+    {
+        self.choose_max_by_with_stats(rng, Ord::cmp)
+    }
+
+    /// Like [`choose_max_by_key`](Self::choose_max_by_key) but also returns the maximum key,
+    /// the number of elements tied for maximum and the total number of elements.
+    pub fn choose_max_by_key_with_stats<B: Ord, R: Rng, F: FnMut(&I::Item) -> B>(
+        self,
+        rng: &mut R,
+        f: F,
+    ) -> Option<Chosen<I::Item, B, C>> {
+        crate::choose_best_by_key_with_stats::<I, B, R, F, C, true>(self.iterator, rng, f)
+    }
+
+    /// Like [`choose_max_by`](Self::choose_max_by) but also returns the number of elements tied for maximum
+    /// and the total number of elements.
+    pub fn choose_max_by_with_stats<R: Rng, F: FnMut(&I::Item, &I::Item) -> Ordering>(
+        self,
+        rng: &mut R,
+        compare: F,
+    ) -> Option<Chosen<I::Item, (), C>>
+    where
+        I::Item: Ord,
+    {
+        crate::choose_best_by_with_stats::<I, R, F, C, true>(self.iterator, rng, compare)
+    }
+
+    /// Like [`choose_min`](Self::choose_min) but also returns the number of elements tied for minimum
+    /// and the total number of elements.
+    pub fn choose_min_with_stats<R: Rng>(self, rng: &mut R) -> Option<Chosen<I::Item, (), C>>
+    where
+        I::Item: Ord,
+    {
+        self.choose_min_by_with_stats(rng, Ord::cmp)
+    }
+
+    /// Like [`choose_min_by_key`](Self::choose_min_by_key) but also returns the minimum key,
+    /// the number of elements tied for minimum and the total number of elements.
+    pub fn choose_min_by_key_with_stats<B: Ord, R: Rng, F: FnMut(&I::Item) -> B>(
+        self,
+        rng: &mut R,
+        f: F,
+    ) -> Option<Chosen<I::Item, B, C>> {
+        crate::choose_best_by_key_with_stats::<I, B, R, F, C, false>(self.iterator, rng, f)
+    }
+
+    /// Like [`choose_min_by`](Self::choose_min_by) but also returns the number of elements tied for minimum
+    /// and the total number of elements.
+    pub fn choose_min_by_with_stats<R: Rng, F: FnMut(&I::Item, &I::Item) -> Ordering>(
+        self,
+        rng: &mut R,
+        compare: F,
+    ) -> Option<Chosen<I::Item, (), C>>
+    where
+        I::Item: Ord,
+    {
+        crate::choose_best_by_with_stats::<I, R, F, C, false>(self.iterator, rng, compare)
+    }
+
+    /// Returns an iterator over unique elements of this iterator.
+    /// Elements are chosen randomly from the duplicates.
+    /// Duplicates are detected using hash and equality.
+    /// Uses the global allocator and the default hasher which is safe from HashDos attacks.
+    #[cfg(any(test, all(feature = "hashbrown", feature = "std")))]
+    pub fn choose_unique<R: Rng>(
+        self,
+        rng: &mut R,
+    ) -> crate::unique::iterators::Unique<I::Item, allocator_api2::alloc::Global, C>
+    where
+        I::Item: Hash + Eq,
+    {
+        let hash_builder = std::collections::hash_map::RandomState::new();
+        let alloc = allocator_api2::alloc::Global;
+        self.choose_unique_with_hasher_in(rng, hash_builder, alloc)
+    }
+
+    /// Returns an iterator over unique elements of this iterator.
+    /// Elements are chosen randomly from the duplicates.
+    /// Duplicates are detected using hash and equality.
+    ///
+    /// You must supply a `BuildHasher` and an `Allocator` to use this.
+    #[cfg(any(test, feature = "hashbrown"))]
+    pub fn choose_unique_with_hasher_in<
+        R: Rng,
+        S: BuildHasher,
+        A: allocator_api2::alloc::Allocator + Clone,
+    >(
+        self,
+        rng: &mut R,
+        hash_builder: S,
+        alloc: A,
+    ) -> crate::unique::iterators::Unique<I::Item, A, C>
+    where
+        I::Item: Hash + Eq,
+    {
+        crate::choose_unique_with_hasher_in::<I, R, S, A, C>(self.iterator, rng, hash_builder, alloc)
+    }
+
+    /// Returns an iterator over unique elements of this iterator.
+    /// Elements are chosen randomly from the duplicates.
+    /// Duplicates are detected by comparing the key they map to with the keying function `get_key` by hash and equality.
+    /// `get_key` is called exactly once for each element.
+    #[cfg(any(test, all(feature = "hashbrown", feature = "std")))]
+    pub fn choose_unique_by_key<R: Rng, K: Eq + Hash, F: FnMut(&I::Item) -> K>(
+        self,
+        rng: &mut R,
+        get_key: F,
+    ) -> crate::unique::iterators::UniqueByKey<K, I::Item, allocator_api2::alloc::Global, C> {
+        let hash_builder = std::collections::hash_map::RandomState::new();
+        let alloc = allocator_api2::alloc::Global;
+        self.choose_unique_by_key_with_hasher_in(rng, get_key, hash_builder, alloc)
+    }
+
+    /// Returns an iterator over unique elements of this iterator.
+    /// Elements are chosen randomly from the duplicates.
+    /// Duplicates are detected by comparing the key they map to with the keying function `get_key` by hash and equality.
+    /// `get_key` is called exactly once for each element.
+    #[cfg(any(test, feature = "hashbrown"))]
+    pub fn choose_unique_by_key_with_hasher_in<
+        R: Rng,
+        K: Eq + Hash,
+        F: FnMut(&I::Item) -> K,
+        S: BuildHasher,
+        A: allocator_api2::alloc::Allocator + Clone,
+    >(
+        self,
+        rng: &mut R,
+        get_key: F,
+        hash_builder: S,
+        alloc: A,
+    ) -> crate::unique::iterators::UniqueByKey<K, I::Item, A, C> {
+        crate::choose_unique_by_key_with_hasher_in::<I, R, K, F, S, A, C>(
+            self.iterator,
+            rng,
+            get_key,
+            hash_builder,
+            alloc,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use crate::Kindness;
+
+    const RUNS: usize = 1000;
+
+    #[test]
+    fn test_wide_counter_matches_usize() {
+        // Below `usize::MAX` elements, counting with a wider type makes exactly the same choices
+        let mut narrow_rng = StdRng::seed_from_u64(123);
+        let mut wide_rng = StdRng::seed_from_u64(123);
+
+        for length in 1..RUNS {
+            let narrow = (0..length).filter(|x| x % 3 == 0);
+            let wide = (0..length).filter(|x| x % 3 == 0);
+            assert_eq!(
+                narrow.choose_item(&mut narrow_rng),
+                wide.wide_counter::<u128>().choose_item(&mut wide_rng)
+            );
+
+            let narrow = (0..length).choose_max_by_key(&mut narrow_rng, |x| x / 10);
+            let wide = (0..length)
+                .wide_counter::<u64>()
+                .choose_max_by_key(&mut wide_rng, |x| x / 10);
+            assert_eq!(narrow, wide);
+
+            let narrow = (0..length).choose_max_by_key_with_stats(&mut narrow_rng, |x| x / 10);
+            let wide = (0..length)
+                .wide_counter::<u64>()
+                .choose_max_by_key_with_stats(&mut wide_rng, |x| x / 10);
+            assert_eq!(
+                narrow.map(|c| (c.item, c.key, c.ties as u64, c.total_seen as u64)),
+                wide.map(|c| (c.item, c.key, c.ties, c.total_seen))
+            );
+
+            let narrow = (0..length).choose_min_by(&mut narrow_rng, |a, b| (a / 10).cmp(&(b / 10)));
+            let wide = (0..length)
+                .wide_counter::<u128>()
+                .choose_min_by(&mut wide_rng, |a, b| (a / 10).cmp(&(b / 10)));
+            assert_eq!(narrow, wide);
+
+            let mut narrow: Vec<_> = (0..length).choose_unique_by_key(&mut narrow_rng, |x| x / 10).collect();
+            let mut wide: Vec<_> = (0..length)
+                .wide_counter::<u64>()
+                .choose_unique_by_key(&mut wide_rng, |x| x / 10)
+                .collect();
+            narrow.sort_unstable();
+            wide.sort_unstable();
+            assert_eq!(narrow, wide);
+        }
+    }
+
+    #[test]
+    #[cfg(target_pointer_width = "64")]
+    fn test_wide_counter_past_u32_max() {
+        // A window hint lets `choose_item` skip most of the elements
+        // so this test can count past `u32::MAX` without taking forever
+        let mut rng = StdRng::seed_from_u64(123);
+        let length = (u32::MAX as usize) * 3;
+        let iterator = WindowHintedRange(0..length, 1 << 30);
+        let element = iterator.wide_counter::<u128>().choose_item(&mut rng).unwrap();
+        assert!(element < length);
+    }
+
+    struct WindowHintedRange(core::ops::Range<usize>, usize);
+
+    impl Iterator for WindowHintedRange {
+        type Item = usize;
+
+        fn next(&mut self) -> Option<Self::Item> {
+            self.0.next()
+        }
+
+        fn nth(&mut self, n: usize) -> Option<Self::Item> {
+            self.0.nth(n)
+        }
+
+        fn size_hint(&self) -> (usize, Option<usize>) {
+            (core::cmp::min(self.0.len(), self.1), None)
+        }
+    }
+}